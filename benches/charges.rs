@@ -0,0 +1,72 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use gasteiger_rs::{Atom, Bond, GasteigerSolver};
+use std::hint::black_box;
+
+fn methane() -> (Vec<Atom>, Vec<Bond>) {
+    let mut atoms = vec![Atom::new(6, 0.0)];
+    atoms.extend((0..4).map(|_| Atom::new(1, 0.0)));
+    let bonds = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+    (atoms, bonds)
+}
+
+fn benzene() -> (Vec<Atom>, Vec<Bond>) {
+    let mut atoms: Vec<Atom> = (0..6).map(|_| Atom::new(6, 0.0)).collect();
+    atoms.extend((0..6).map(|_| Atom::new(1, 0.0)));
+    let mut bonds = Vec::new();
+    for i in 0..6 {
+        bonds.push(Bond::new(i, (i + 1) % 6, 1.5));
+        bonds.push(Bond::new(i, i + 6, 1.0));
+    }
+    (atoms, bonds)
+}
+
+/// Linear alkane CₙH₂ₙ₊₂: carbons first, then hydrogens.
+fn alkane(n_carbons: usize) -> (Vec<Atom>, Vec<Bond>) {
+    let mut atoms: Vec<Atom> = (0..n_carbons).map(|_| Atom::new(6, 0.0)).collect();
+    let mut bonds: Vec<Bond> = (1..n_carbons).map(|c| Bond::new(c - 1, c, 1.0)).collect();
+    for c in 0..n_carbons {
+        let hydrogens = if n_carbons == 1 { 4 } else if c == 0 || c == n_carbons - 1 { 3 } else { 2 };
+        for _ in 0..hydrogens {
+            bonds.push(Bond::new(c, atoms.len(), 1.0));
+            atoms.push(Atom::new(1, 0.0));
+        }
+    }
+    (atoms, bonds)
+}
+
+fn bench_compute_charges(c: &mut Criterion) {
+    let solver = GasteigerSolver::default();
+    let mut group = c.benchmark_group("compute_charges");
+
+    let named = [("methane", methane()), ("benzene", benzene())];
+    for (name, (atoms, bonds)) in &named {
+        group.bench_function(*name, |b| b.iter(|| solver.compute_charges(black_box(atoms), black_box(bonds))));
+    }
+    for n_carbons in [100, 1_000, 10_000] {
+        let (atoms, bonds) = alkane(n_carbons);
+        group.bench_with_input(BenchmarkId::new("alkane", n_carbons), &(atoms, bonds), |b, (atoms, bonds)| {
+            b.iter(|| solver.compute_charges(black_box(atoms), black_box(bonds)))
+        });
+    }
+    group.finish();
+}
+
+/// Memoized parameter resolution (`compute_charges`) against perceiving and
+/// looking up parameters atom by atom.
+fn bench_parameter_memoization(c: &mut Criterion) {
+    let solver = GasteigerSolver::default();
+    let mut group = c.benchmark_group("parameter_memoization");
+    let (atoms, bonds) = alkane(2_000);
+
+    group.bench_function("memoized", |b| b.iter(|| solver.compute_charges(black_box(&atoms), black_box(&bonds))));
+    group.bench_function("per_atom", |b| {
+        b.iter(|| {
+            let hybridizations = solver.perceive_hybridizations(black_box(&atoms), black_box(&bonds));
+            solver.compute_charges_with_hybridizations(&atoms, &bonds, &hybridizations)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_compute_charges, bench_parameter_memoization);
+criterion_main!(benches);
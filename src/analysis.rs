@@ -0,0 +1,169 @@
+//! Descriptors and helpers operating on computed charge vectors.
+
+use std::collections::HashMap;
+
+use crate::error::SolverError;
+use crate::molecule::covalent_radius;
+use crate::traits::{GasteigerAtom, GasteigerBond};
+
+/// Returns the indices of the most negative and most positive charges, `(min, max)`.
+///
+/// Ties are resolved in favour of the lowest index.
+///
+/// # Panics
+///
+/// Panics if `charges` is empty.
+pub fn extrema(charges: &[f64]) -> (usize, usize) {
+    assert!(!charges.is_empty(), "extrema of an empty charge vector");
+    let mut min_idx = 0;
+    let mut max_idx = 0;
+    for (i, &q) in charges.iter().enumerate().skip(1) {
+        if q < charges[min_idx] { min_idx = i; }
+        if q > charges[max_idx] { max_idx = i; }
+    }
+    (min_idx, max_idx)
+}
+
+/// Sum of absolute charges, Σ|qᵢ|, a simple scalar proxy for overall polarity.
+pub fn total_absolute_charge(charges: &[f64]) -> f64 {
+    charges.iter().map(|q| q.abs()).sum()
+}
+
+/// Rounds every charge to `decimals` decimal places in place.
+///
+/// Ties are rounded half-to-even (banker's rounding), so e.g. `0.0125` becomes
+/// `0.012` at three decimals and a long list of ties carries no systematic bias.
+pub fn round_charges(charges: &mut [f64], decimals: u32) {
+    let factor = 10f64.powi(decimals as i32);
+    for q in charges.iter_mut() {
+        *q = (*q * factor).round_ties_even() / factor;
+    }
+}
+
+/// Whether two charge vectors have the same length and agree element-wise within `tol`.
+///
+/// `NaN` never compares equal.
+pub fn charges_approx_eq(a: &[f64], b: &[f64], tol: f64) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() <= tol)
+}
+
+/// Asserts that two charge vectors agree within a tolerance (default `1e-6`),
+/// using `charges_approx_eq`.
+///
+/// ```
+/// gasteiger_rs::assert_charges_approx_eq!([0.1, -0.1], [0.1000001, -0.1]);
+/// gasteiger_rs::assert_charges_approx_eq!([0.1, -0.1], [0.11, -0.1], 0.02);
+/// ```
+#[macro_export]
+macro_rules! assert_charges_approx_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_charges_approx_eq!($left, $right, 1e-6)
+    };
+    ($left:expr, $right:expr, $tol:expr $(,)?) => {{
+        let (left, right, tol): (&[f64], &[f64], f64) = (&$left[..], &$right[..], $tol);
+        assert!(
+            $crate::analysis::charges_approx_eq(left, right, tol),
+            "charges differ by more than {}:\n  left: {:?}\n right: {:?}",
+            tol,
+            left,
+            right
+        );
+    }};
+}
+
+/// Indices of atoms whose charge lies more than `z_thresh` standard deviations
+/// from the mean charge of the same element within the molecule.
+///
+/// Statistics are per element, using the population standard deviation. An
+/// element with a single atom, or whose atoms all carry the same charge, has no
+/// spread to compare against and never produces outliers. Indices are ascending.
+pub fn charge_outliers<A: GasteigerAtom>(atoms: &[A], charges: &[f64], z_thresh: f64) -> Result<Vec<usize>, SolverError> {
+    if charges.len() != atoms.len() {
+        return Err(SolverError::AtomCountMismatch { expected: atoms.len(), found: charges.len() });
+    }
+    let mut by_element: HashMap<usize, Vec<f64>> = HashMap::new();
+    for (atom, &q) in atoms.iter().zip(charges) {
+        by_element.entry(atom.atomic_number()).or_default().push(q);
+    }
+    let stats: HashMap<usize, (f64, f64)> = by_element
+        .into_iter()
+        .map(|(z, qs)| {
+            let n = qs.len() as f64;
+            let mean = qs.iter().sum::<f64>() / n;
+            let var = qs.iter().map(|q| (q - mean) * (q - mean)).sum::<f64>() / n;
+            (z, (mean, var.sqrt()))
+        })
+        .collect();
+    Ok(atoms
+        .iter()
+        .zip(charges)
+        .enumerate()
+        .filter(|&(_, (atom, &q))| {
+            let (mean, std) = stats[&atom.atomic_number()];
+            std > 0.0 && (q - mean).abs() > z_thresh * std
+        })
+        .map(|(i, _)| i)
+        .collect())
+}
+
+/// Each atom's charge weighted by a crude estimate of its exposed surface area.
+///
+/// The weight is `4π r² / (1 + n)`, the sphere area of the atom's radius `r`
+/// (Å) shared with its `n` bonded neighbors, so terminal atoms count more than
+/// buried ones. This is a connectivity-only heuristic, not a computed solvent
+/// accessible surface. A radius that is missing (`radii` shorter than `atoms`),
+/// non-finite or not positive falls back to `molecule::covalent_radius`; an atom
+/// with neither gets `0.0`. Bonds with an out-of-range endpoint are ignored.
+pub fn surface_weighted_charges<A, B>(atoms: &[A], bonds: &[B], charges: &[f64], radii: &[f64]) -> Result<Vec<f64>, SolverError>
+where
+    A: GasteigerAtom,
+    B: GasteigerBond,
+{
+    if charges.len() != atoms.len() {
+        return Err(SolverError::AtomCountMismatch { expected: atoms.len(), found: charges.len() });
+    }
+    let mut neighbors = vec![0usize; atoms.len()];
+    for (i, j) in bonds.iter().map(|b| b.atom_indices()) {
+        if i < atoms.len() && j < atoms.len() && i != j {
+            neighbors[i] += 1;
+            neighbors[j] += 1;
+        }
+    }
+    Ok(atoms
+        .iter()
+        .enumerate()
+        .map(|(i, atom)| {
+            let radius = radii
+                .get(i)
+                .copied()
+                .filter(|r| r.is_finite() && *r > 0.0)
+                .or_else(|| covalent_radius(atom.atomic_number()));
+            radius.map_or(0.0, |r| charges[i] * 4.0 * std::f64::consts::PI * r * r / (1 + neighbors[i]) as f64)
+        })
+        .collect())
+}
+
+/// Root-mean-square deviation between computed and reference charges.
+/// Returns `0.0` for two empty slices.
+pub fn rmsd_to_reference(computed: &[f64], reference: &[f64]) -> Result<f64, SolverError> {
+    check_lengths(computed, reference)?;
+    if computed.is_empty() {
+        return Ok(0.0);
+    }
+    let sum_sq: f64 = computed.iter().zip(reference).map(|(c, r)| (c - r) * (c - r)).sum();
+    Ok((sum_sq / computed.len() as f64).sqrt())
+}
+
+/// Largest absolute per-atom deviation between computed and reference charges.
+/// Returns `0.0` for two empty slices.
+pub fn max_abs_error(computed: &[f64], reference: &[f64]) -> Result<f64, SolverError> {
+    check_lengths(computed, reference)?;
+    Ok(computed.iter().zip(reference).map(|(c, r)| (c - r).abs()).fold(0.0, f64::max))
+}
+
+fn check_lengths(computed: &[f64], reference: &[f64]) -> Result<(), SolverError> {
+    if computed.len() != reference.len() {
+        return Err(SolverError::AtomCountMismatch { expected: reference.len(), found: computed.len() });
+    }
+    Ok(())
+}
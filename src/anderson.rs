@@ -0,0 +1,91 @@
+//! Anderson mixing of the undamped PEOE fixed-point map.
+
+use std::collections::VecDeque;
+
+use crate::linearized::solve_dense;
+use crate::model::ElectronegativityModel;
+use crate::solver::{apply_bounds, unfrozen_neighbors, GasteigerSolver, Topology};
+
+impl<M: ElectronegativityModel> GasteigerSolver<M> {
+    /// Anderson-accelerated iteration on `q ← q + β r(q)`, where `r` is the
+    /// undamped transfer residual and `β = damping`. Returns `(iterations, converged)`.
+    ///
+    /// Each step combines the last `depth` iterate and residual differences by
+    /// least squares. When that system is singular (e.g. on the first step, or
+    /// once the differences stop being independent) the history is dropped and
+    /// the plain damped step `β r(q)` is taken instead. `charge_bounds` are
+    /// applied after every mixed step, and convergence is measured on the
+    /// change that remains after clamping.
+    pub(crate) fn equalize_anderson<F>(&self, charges: &mut [f64], topology: &Topology, depth: usize, mut on_iteration: F) -> (usize, bool)
+    where
+        F: FnMut(usize, &[f64]),
+    {
+        let beta = self.damping;
+        let mut history: VecDeque<(Vec<f64>, Vec<f64>)> = VecDeque::with_capacity(depth + 1);
+        let mut previous: Option<(Vec<f64>, Vec<f64>)> = None;
+        let bounded_neighbors = topology.bounds.iter().any(Option::is_some).then(|| unfrozen_neighbors(topology));
+
+        for iteration in 0..self.iterations {
+            let residual = self.transfer_residual(charges, topology);
+            if let Some((q_prev, r_prev)) = previous.take() {
+                let dq = charges.iter().zip(&q_prev).map(|(q, p)| q - p).collect();
+                let dr = residual.iter().zip(&r_prev).map(|(r, p)| r - p).collect();
+                history.push_back((dq, dr));
+                if history.len() > depth {
+                    history.pop_front();
+                }
+            }
+
+            let mut step: Vec<f64> = residual.iter().map(|r| beta * r).collect();
+            if !history.is_empty() {
+                match mixing_coefficients(&history, &residual) {
+                    Some(gamma) => {
+                        for ((dq, dr), g) in history.iter().zip(gamma) {
+                            for (s, (q, r)) in step.iter_mut().zip(dq.iter().zip(dr)) {
+                                *s -= g * (q + beta * r);
+                            }
+                        }
+                    }
+                    None => history.clear(),
+                }
+            }
+
+            previous = Some((charges.to_vec(), residual));
+            for (q, s) in charges.iter_mut().zip(&step) {
+                *q += s;
+            }
+            if let (Some(neighbors), Some((q_prev, _))) = (&bounded_neighbors, &previous) {
+                apply_bounds(charges, &topology.bounds, &topology.frozen, neighbors);
+                for ((s, q), p) in step.iter_mut().zip(charges.iter()).zip(q_prev) {
+                    *s = q - p;
+                }
+            }
+            on_iteration(iteration + 1, charges);
+
+            if self.tolerance.is_some_and(|tol| self.convergence_norm.measure(&step) < tol) {
+                return (iteration + 1, true);
+            }
+        }
+        (self.iterations, false)
+    }
+}
+
+/// Least-squares `γ` minimizing `|r - Σ γₐ Δrₐ|` via the normal equations,
+/// normalized by their largest diagonal entry. `None` if the system is singular.
+fn mixing_coefficients(history: &VecDeque<(Vec<f64>, Vec<f64>)>, residual: &[f64]) -> Option<Vec<f64>> {
+    let dot = |x: &[f64], y: &[f64]| x.iter().zip(y).map(|(a, b)| a * b).sum::<f64>();
+    let mut matrix: Vec<Vec<f64>> = history
+        .iter()
+        .map(|(_, a)| history.iter().map(|(_, b)| dot(a, b)).collect())
+        .collect();
+    let mut rhs: Vec<f64> = history.iter().map(|(_, a)| dot(a, residual)).collect();
+    let scale = (0..matrix.len()).map(|k| matrix[k][k]).fold(0.0, f64::max);
+    if scale == 0.0 {
+        return None;
+    }
+    for (row, b) in matrix.iter_mut().zip(rhs.iter_mut()) {
+        row.iter_mut().for_each(|x| *x /= scale);
+        *b /= scale;
+    }
+    solve_dense(matrix, rhs)
+}
@@ -0,0 +1,147 @@
+//! Least-squares refitting of Gasteiger parameters against reference charges.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::SolverError;
+use crate::model::ElectronegativityModel;
+use crate::parameters::{GasteigerParams, Hybridization, ParameterSet};
+use crate::solver::GasteigerSolver;
+use crate::traits::{GasteigerAtom, GasteigerBond, ResolutionObserver};
+
+/// Central-difference step for the loss gradient.
+const GRADIENT_STEP: f64 = 1e-5;
+
+/// Largest change of any single coefficient in the first trial of a line search.
+const MAX_TRIAL_MOVE: f64 = 0.5;
+
+/// Trial step halvings before a line search gives up.
+const MAX_HALVINGS: usize = 40;
+
+/// A molecule with its reference charges, as passed to `GasteigerSolver::calibrate`.
+pub type CalibrationSample<'a, A, B> = (&'a [A], &'a [B], &'a [f64]);
+
+impl<M: ElectronegativityModel + Clone> GasteigerSolver<M> {
+    /// Refits `a`, `b` and `c` for the atoms of `elements` so that the solver's
+    /// charges approach the reference charges of every sample.
+    ///
+    /// Every `(element, perceived hybridization)` context that occurs among the
+    /// samples is fitted separately, starting from the parameters this solver
+    /// currently resolves for it. The loss is the mean squared charge error over
+    /// all atoms of all samples, minimized by steepest descent with a
+    /// central-difference gradient and a backtracking line search, so the loss
+    /// never increases. The search stops after `max_steps` steps or once no
+    /// step improves the loss.
+    ///
+    /// Returns only the fitted entries, keyed by the perceived hybridization. To
+    /// use them, layer them over the current provider, e.g. extend
+    /// `ParameterSet::builtin().entries` with them. Hydrogens covered by
+    /// `polar_hydrogen_params` ignore the provider and are not fitted.
+    pub fn calibrate<A, B>(
+        &self,
+        samples: &[CalibrationSample<'_, A, B>],
+        elements: &[usize],
+        max_steps: usize,
+    ) -> Result<ParameterSet, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let mut collector = ContextCollector { elements, contexts: HashMap::new() };
+        for &(atoms, bonds, reference) in samples {
+            if reference.len() != atoms.len() {
+                return Err(SolverError::AtomCountMismatch { expected: atoms.len(), found: reference.len() });
+            }
+            self.compute_charges_observed(atoms, bonds, &mut collector);
+        }
+        let mut contexts: Vec<(usize, Hybridization)> = collector.contexts.keys().copied().collect();
+        contexts.sort_by_key(|&(z, h)| (z, Hybridization::ALL.iter().position(|&x| x == h)));
+        let mut theta: Vec<f64> = contexts
+            .iter()
+            .flat_map(|key| {
+                let p = collector.contexts[key];
+                [p.a, p.b, p.c]
+            })
+            .collect();
+
+        let loss = |theta: &[f64]| {
+            let solver = self.with_overrides(&contexts, theta);
+            let (mut sum_sq, mut count) = (0.0, 0usize);
+            for &(atoms, bonds, reference) in samples {
+                let charges = solver.compute_charges(atoms, bonds);
+                sum_sq += charges.iter().zip(reference).map(|(q, r)| (q - r) * (q - r)).sum::<f64>();
+                count += charges.len();
+            }
+            if count == 0 { 0.0 } else { sum_sq / count as f64 }
+        };
+
+        let mut current = loss(&theta);
+        for _ in 0..max_steps {
+            let mut probe = theta.clone();
+            let gradient: Vec<f64> = (0..theta.len())
+                .map(|k| {
+                    probe[k] = theta[k] + GRADIENT_STEP;
+                    let plus = loss(&probe);
+                    probe[k] = theta[k] - GRADIENT_STEP;
+                    let minus = loss(&probe);
+                    probe[k] = theta[k];
+                    (plus - minus) / (2.0 * GRADIENT_STEP)
+                })
+                .collect();
+            let largest = gradient.iter().fold(0.0f64, |m, g| m.max(g.abs()));
+            if largest == 0.0 {
+                break;
+            }
+
+            let mut step = MAX_TRIAL_MOVE / largest;
+            let mut improved = None;
+            for _ in 0..MAX_HALVINGS {
+                let trial: Vec<f64> = theta.iter().zip(&gradient).map(|(t, g)| t - step * g).collect();
+                let value = loss(&trial);
+                if value < current {
+                    improved = Some((trial, value));
+                    break;
+                }
+                step *= 0.5;
+            }
+            match improved {
+                Some((trial, value)) => (theta, current) = (trial, value),
+                None => break,
+            }
+        }
+
+        let entries = contexts
+            .iter()
+            .zip(theta.chunks_exact(3))
+            .map(|(&key, abc)| (key, GasteigerParams { a: abc[0], b: abc[1], c: abc[2] }))
+            .collect();
+        Ok(ParameterSet { entries })
+    }
+
+    /// A copy of this solver whose provider answers `contexts` from `theta`
+    /// (three coefficients per context) and defers to the current provider otherwise.
+    fn with_overrides(&self, contexts: &[(usize, Hybridization)], theta: &[f64]) -> Self {
+        let table: HashMap<(usize, Hybridization), GasteigerParams> = contexts
+            .iter()
+            .zip(theta.chunks_exact(3))
+            .map(|(&key, abc)| (key, GasteigerParams { a: abc[0], b: abc[1], c: abc[2] }))
+            .collect();
+        let base = Arc::clone(&self.param_provider);
+        let provider = move |z: usize, h: Hybridization| table.get(&(z, h)).copied().or_else(|| base.params(z, h));
+        GasteigerSolver { param_provider: Arc::new(provider), ..self.clone() }
+    }
+}
+
+/// Records the first resolved parameters of every context of the selected elements.
+struct ContextCollector<'a> {
+    elements: &'a [usize],
+    contexts: HashMap<(usize, Hybridization), GasteigerParams>,
+}
+
+impl ResolutionObserver for ContextCollector<'_> {
+    fn on_atom_resolved(&mut self, _index: usize, atomic_number: usize, guessed: Hybridization, resolved: Option<&GasteigerParams>) {
+        if let Some(&params) = resolved.filter(|_| self.elements.contains(&atomic_number)) {
+            self.contexts.entry((atomic_number, guessed)).or_insert(params);
+        }
+    }
+}
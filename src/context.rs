@@ -0,0 +1,148 @@
+//! Reusable solver state for repeated solves on the same topology.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::model::ElectronegativityModel;
+use crate::solver::{GasteigerSolver, Topology};
+use crate::traits::{effective_order, GasteigerAtom, GasteigerBond};
+
+/// Caches the charge-independent work of a solve (hybridization perception,
+/// parameter lookup, χ(+1) denominators and the filtered bond list) so that
+/// molecules whose formal charges change but whose topology does not can be
+/// re-solved cheaply.
+///
+/// Lifecycle:
+/// 1. `SolverContext::new(&solver)` creates an empty context.
+/// 2. The first `solve` perceives the topology and caches it.
+/// 3. Later `solve` calls reuse the cache and only re-read formal charges.
+/// 4. Call `invalidate` whenever atoms or bonds are added, removed or re-typed;
+///    the next `solve` rebuilds the cache.
+///
+/// The context never checks whether the topology changed; passing a different
+/// molecule without invalidating gives wrong charges.
+#[derive(Debug, Clone)]
+pub struct SolverContext<'a, M> {
+    solver: &'a GasteigerSolver<M>,
+    topology: Option<Topology>,
+}
+
+impl<'a, M: ElectronegativityModel> SolverContext<'a, M> {
+    pub fn new(solver: &'a GasteigerSolver<M>) -> Self {
+        Self { solver, topology: None }
+    }
+
+    /// Solves for charges, reusing the cached topology when available.
+    pub fn solve<A, B>(&mut self, atoms: &[A], bonds: &[B]) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let solver = self.solver;
+        let topology = self.topology.get_or_insert_with(|| solver.prepare(atoms, bonds));
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        solver.equalize_molecule(&mut charges, topology);
+        solver.finish(atoms, bonds, topology, &mut charges);
+        charges
+    }
+
+    /// Discards the cached topology. Call this after any structural change.
+    pub fn invalidate(&mut self) {
+        self.topology = None;
+    }
+
+    /// Whether a cached topology is currently held.
+    pub fn is_cached(&self) -> bool {
+        self.topology.is_some()
+    }
+}
+
+/// Memoizes whole solves, keyed on a 64-bit hash of everything about a
+/// molecule that can change its charges: atom count, each atom's element,
+/// formal charge, virtual-site and frozen flags and explicit hybridization, and
+/// each bond's endpoints and effective order.
+///
+/// Invalidation:
+/// - Molecules are rehashed on every call, so edits to atoms or bonds are
+///   always picked up; nothing needs to be invalidated for them.
+/// - The solver's configuration is not part of the key. The cache borrows the
+///   solver immutably, so it cannot change underneath it, but a cache must not
+///   be reused with a different solver; build a new one instead.
+/// - Entries are never evicted; call `clear` to bound memory.
+///
+/// Distinct molecules colliding on the 64-bit key would share an entry; with
+/// `std`'s SipHash this is vanishingly unlikely for realistic cache sizes.
+#[derive(Debug, Clone)]
+pub struct ChargeCache<'a, M> {
+    solver: &'a GasteigerSolver<M>,
+    entries: HashMap<u64, Vec<f64>>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<'a, M: ElectronegativityModel> ChargeCache<'a, M> {
+    pub fn new(solver: &'a GasteigerSolver<M>) -> Self {
+        Self { solver, entries: HashMap::new(), hits: 0, misses: 0 }
+    }
+
+    /// Returns the cached charges for this molecule, solving and storing them on a miss.
+    pub fn solve<A, B>(&mut self, atoms: &[A], bonds: &[B]) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let key = molecule_hash(atoms, bonds);
+        if let Some(charges) = self.entries.get(&key) {
+            self.hits += 1;
+            return charges.clone();
+        }
+        self.misses += 1;
+        let charges = self.solver.compute_charges(atoms, bonds);
+        self.entries.insert(key, charges.clone());
+        charges
+    }
+
+    /// Number of `solve` calls served from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of `solve` calls that had to run the solver.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Number of cached molecules.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drops every cached entry; the hit and miss counters are kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn molecule_hash<A: GasteigerAtom, B: GasteigerBond>(atoms: &[A], bonds: &[B]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    atoms.len().hash(&mut hasher);
+    for atom in atoms {
+        atom.atomic_number().hash(&mut hasher);
+        atom.formal_charge().to_bits().hash(&mut hasher);
+        atom.is_virtual_site().hash(&mut hasher);
+        atom.is_frozen().hash(&mut hasher);
+        atom.hybridization().hash(&mut hasher);
+        atom.atom_type().hash(&mut hasher);
+    }
+    bonds.len().hash(&mut hasher);
+    for bond in bonds {
+        bond.atom_indices().hash(&mut hasher);
+        effective_order(bond).to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
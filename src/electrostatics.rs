@@ -0,0 +1,121 @@
+//! Electrostatic properties derived from charges and 3D coordinates.
+//!
+//! Charges are in elementary charges (e) and coordinates in ångströms (Å).
+
+use crate::error::SolverError;
+
+/// Coulomb constant `1 / (4π ε₀)` in eV·Å/e².
+pub const COULOMB_CONSTANT: f64 = 14.399645;
+
+/// Distance (Å) below which a probe point is taken to coincide with an atom.
+const COINCIDENT_DISTANCE: f64 = 1e-8;
+
+/// Dipole moment Σ qᵢ rᵢ in e·Å (1 e·Å ≈ 4.803 D).
+pub fn dipole_moment(charges: &[f64], coords: &[[f64; 3]]) -> Result<[f64; 3], SolverError> {
+    check_coords(charges, coords)?;
+    let mut mu = [0.0; 3];
+    for (q, r) in charges.iter().zip(coords) {
+        for k in 0..3 {
+            mu[k] += q * r[k];
+        }
+    }
+    Ok(mu)
+}
+
+/// Scales charges so the dipole moment best matches `target_dipole` (e·Å).
+///
+/// Charges are written as `qᵢ = m + (qᵢ - m)` with `m` the mean charge, and only
+/// the deviations are scaled: `qᵢ' = m + s (qᵢ - m)`. This preserves the total
+/// charge. The dipole is then linear in `s`, `μ(s) = μ₀ + s d`, and `s` is the
+/// least-squares solution `s = d · (target - μ₀) / |d|²`. If the deviations
+/// carry no dipole (`d = 0`) the charges are left unchanged and `1.0` is
+/// returned. Returns the applied factor `s`.
+pub fn scale_charges_to_dipole(charges: &mut [f64], coords: &[[f64; 3]], target_dipole: [f64; 3]) -> Result<f64, SolverError> {
+    check_coords(charges, coords)?;
+    if charges.is_empty() {
+        return Ok(1.0);
+    }
+    let mean = charges.iter().sum::<f64>() / charges.len() as f64;
+
+    let mut base = [0.0; 3];
+    let mut d = [0.0; 3];
+    for (q, r) in charges.iter().zip(coords) {
+        for k in 0..3 {
+            base[k] += mean * r[k];
+            d[k] += (q - mean) * r[k];
+        }
+    }
+
+    let d_sq: f64 = d.iter().map(|x| x * x).sum();
+    if d_sq < 1e-24 {
+        return Ok(1.0);
+    }
+    let scale = (0..3).map(|k| d[k] * (target_dipole[k] - base[k])).sum::<f64>() / d_sq;
+    for q in charges.iter_mut() {
+        *q = mean + scale * (*q - mean);
+    }
+    Ok(scale)
+}
+
+/// Coulomb potential `V = k Σ qᵢ / |p - rᵢ|` at `point`, in V (eV/e).
+///
+/// Atoms closer than 1e-8 Å to `point` are left out of the sum, as in
+/// [`electric_field_at`].
+pub fn electrostatic_potential_at(charges: &[f64], coords: &[[f64; 3]], point: [f64; 3]) -> Result<f64, SolverError> {
+    check_coords(charges, coords)?;
+    let mut potential = 0.0;
+    for (q, r) in charges.iter().zip(coords) {
+        let dist = (0..3).map(|k| (point[k] - r[k]).powi(2)).sum::<f64>().sqrt();
+        if dist >= COINCIDENT_DISTANCE {
+            potential += COULOMB_CONSTANT * q / dist;
+        }
+    }
+    Ok(potential)
+}
+
+/// Coulomb field `E = k Σ qᵢ (p - rᵢ) / |p - rᵢ|³` at `point`, in V/Å.
+///
+/// `k` is [`COULOMB_CONSTANT`], so with charges in e and distances in Å the
+/// field comes out in eV/(e·Å) = V/Å. An atom closer than 1e-8 Å to `point`
+/// has a singular field there and is left out of the sum.
+pub fn electric_field_at(charges: &[f64], coords: &[[f64; 3]], point: [f64; 3]) -> Result<[f64; 3], SolverError> {
+    check_coords(charges, coords)?;
+    let mut field = [0.0; 3];
+    for (q, r) in charges.iter().zip(coords) {
+        let d = [point[0] - r[0], point[1] - r[1], point[2] - r[2]];
+        let dist = d.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if dist < COINCIDENT_DISTANCE {
+            continue;
+        }
+        let factor = COULOMB_CONSTANT * q / (dist * dist * dist);
+        for k in 0..3 {
+            field[k] += factor * d[k];
+        }
+    }
+    Ok(field)
+}
+
+/// Scales charges by `1 / √ε` as a crude stand-in for dielectric screening.
+///
+/// This is an approximation, not a solvation model: it uniformly shrinks every
+/// charge (and hence the total charge) so that pairwise Coulomb energies
+/// `qᵢqⱼ/r` drop by `1/ε`, mimicking a continuum of relative permittivity
+/// `epsilon`. `epsilon = 1` (vacuum) leaves the charges unchanged.
+///
+/// # Panics
+///
+/// Panics if `epsilon` is not a finite positive number.
+pub fn scale_for_dielectric(charges: &mut [f64], epsilon: f64) {
+    assert!(epsilon.is_finite() && epsilon > 0.0, "dielectric constant must be positive, got {}", epsilon);
+    let factor = epsilon.sqrt().recip();
+    for q in charges.iter_mut() {
+        *q *= factor;
+    }
+}
+
+fn check_coords(charges: &[f64], coords: &[[f64; 3]]) -> Result<(), SolverError> {
+    if coords.len() != charges.len() {
+        return Err(SolverError::AtomCountMismatch { expected: charges.len(), found: coords.len() });
+    }
+    Ok(())
+}
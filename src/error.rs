@@ -0,0 +1,77 @@
+use std::fmt;
+
+/// Errors reported by the checked solver APIs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    /// A bond refers to an atom index outside the atom slice.
+    BondIndexOutOfRange { bond: usize, index: usize, n_atoms: usize },
+    /// A formal charge is non-finite or exceeds `MAX_FORMAL_CHARGE` in magnitude.
+    FormalChargeOutOfRange { index: usize, value: f32 },
+    /// Inputs that must describe the same atoms have different atom counts.
+    AtomCountMismatch { expected: usize, found: usize },
+    /// No parameters exist for an atom and the solver is configured to reject it.
+    UnknownElement { index: usize, atomic_number: usize },
+    /// Parallel bond arrays have different lengths.
+    BondCountMismatch { expected: usize, found: usize },
+    /// An atom index passed to the solver is outside the atom slice.
+    AtomIndexOutOfRange { index: usize, n_atoms: usize },
+    /// The formal charges do not add up to the requested net charge.
+    NetChargeMismatch { expected: f64, found: f64 },
+    /// A ring mixes aromatic (1.5) bonds with single/double labels.
+    InconsistentAromaticRing { atoms: Vec<usize> },
+    /// The bond orders around an atom add up to more than its element allows.
+    Overvalence { index: usize, z: usize, total_order: f32 },
+    /// An atom with charge bounds has bonded neighbors, but all of them are
+    /// frozen, so no clipped charge could be redistributed.
+    BoundedAtomWithoutAbsorber { index: usize },
+    /// An atom has charge bounds, but the solve mode does not enforce them
+    /// (`SolveMode::Linearized`).
+    UnsupportedChargeBounds { index: usize },
+    /// A temperature is zero, negative or not finite.
+    InvalidTemperature { value: f64 },
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::BondIndexOutOfRange { bond, index, n_atoms } => {
+                write!(f, "bond {} references atom {} but only {} atoms exist", bond, index, n_atoms)
+            }
+            SolverError::FormalChargeOutOfRange { index, value } => {
+                write!(f, "atom {} has out-of-range formal charge {}", index, value)
+            }
+            SolverError::AtomCountMismatch { expected, found } => {
+                write!(f, "expected {} atoms but found {}", expected, found)
+            }
+            SolverError::UnknownElement { index, atomic_number } => {
+                write!(f, "atom {} has element {} with no Gasteiger parameters", index, atomic_number)
+            }
+            SolverError::BondCountMismatch { expected, found } => {
+                write!(f, "expected {} bond entries but found {}", expected, found)
+            }
+            SolverError::AtomIndexOutOfRange { index, n_atoms } => {
+                write!(f, "atom index {} out of range for {} atoms", index, n_atoms)
+            }
+            SolverError::NetChargeMismatch { expected, found } => {
+                write!(f, "formal charges sum to {} but the net charge is {}", found, expected)
+            }
+            SolverError::InconsistentAromaticRing { atoms } => {
+                write!(f, "ring {:?} mixes aromatic and non-aromatic bond orders", atoms)
+            }
+            SolverError::Overvalence { index, z, total_order } => {
+                write!(f, "atom {} (element {}) has total bond order {}", index, z, total_order)
+            }
+            SolverError::BoundedAtomWithoutAbsorber { index } => {
+                write!(f, "atom {} has charge bounds but every bonded neighbor is frozen", index)
+            }
+            SolverError::UnsupportedChargeBounds { index } => {
+                write!(f, "atom {} has charge bounds, which the linearized solve does not enforce", index)
+            }
+            SolverError::InvalidTemperature { value } => {
+                write!(f, "temperature must be finite and positive, got {} K", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
@@ -0,0 +1,58 @@
+//! `petgraph` integration (requires the `petgraph` feature).
+//!
+//! Node weights are atoms and edge weights carry bond orders; bond indices are
+//! taken from the graph's edge endpoints.
+
+use petgraph::graph::{Graph, IndexType};
+use petgraph::visit::EdgeRef;
+use petgraph::EdgeType;
+
+use crate::model::ElectronegativityModel;
+use crate::molecule::Bond;
+use crate::solver::GasteigerSolver;
+use crate::traits::{GasteigerAtom, PartialChargeSink};
+
+/// Edge weights that carry a bond order.
+pub trait EdgeBondOrder {
+    fn bond_order(&self) -> f32;
+}
+
+impl EdgeBondOrder for f32 {
+    fn bond_order(&self) -> f32 { *self }
+}
+
+impl EdgeBondOrder for f64 {
+    fn bond_order(&self) -> f32 { *self as f32 }
+}
+
+impl<M: ElectronegativityModel> GasteigerSolver<M> {
+    /// Computes charges for a molecular graph, indexed by `NodeIndex::index()`.
+    pub fn compute_charges_for_graph<A, B, Ty, Ix>(&self, graph: &Graph<A, B, Ty, Ix>) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: EdgeBondOrder,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let atoms: Vec<&A> = graph.node_weights().collect();
+        let bonds: Vec<Bond> = graph
+            .edge_references()
+            .map(|e| Bond::new(e.source().index(), e.target().index(), e.weight().bond_order()))
+            .collect();
+        self.compute_charges(&atoms, &bonds)
+    }
+
+    /// Computes charges for a molecular graph and stores them on its nodes.
+    pub fn assign_charges_to_graph<A, B, Ty, Ix>(&self, graph: &mut Graph<A, B, Ty, Ix>)
+    where
+        A: GasteigerAtom + PartialChargeSink,
+        B: EdgeBondOrder,
+        Ty: EdgeType,
+        Ix: IndexType,
+    {
+        let charges = self.compute_charges_for_graph(graph);
+        for (node, charge) in graph.node_weights_mut().zip(charges) {
+            node.set_partial_charge(charge);
+        }
+    }
+}
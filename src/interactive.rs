@@ -0,0 +1,85 @@
+//! Stateful charge assignment for molecule editors.
+
+use crate::model::{ElectronegativityModel, QuadraticModel};
+use crate::molecule::Bond;
+use crate::parameters::GasteigerParams;
+use crate::solver::GasteigerSolver;
+use crate::traits::GasteigerAtom;
+
+/// Keeps charges up to date while bonds are added and removed one at a time.
+///
+/// Parameters are resolved once for every atom when the charger is created.
+/// Each `add_bond`/`remove_bond` re-perceives only the two atoms of the changed
+/// bond, keeps every other atom's cached parameters, and re-runs the solve.
+/// Atoms themselves are fixed for the charger's lifetime.
+#[derive(Debug, Clone)]
+pub struct InteractiveCharger<'a, A, M = QuadraticModel> {
+    solver: &'a GasteigerSolver<M>,
+    atoms: Vec<A>,
+    bonds: Vec<Bond>,
+    params: Vec<Option<GasteigerParams>>,
+    charges: Vec<f64>,
+}
+
+impl<'a, A: GasteigerAtom, M: ElectronegativityModel> InteractiveCharger<'a, A, M> {
+    /// Starts with no bonds; charges equal the formal charges.
+    pub fn new(solver: &'a GasteigerSolver<M>, atoms: Vec<A>) -> Self {
+        let bonds: Vec<Bond> = Vec::new();
+        let params = solver.resolve_params(&atoms, &bonds);
+        let mut charger = Self { solver, atoms, bonds, params, charges: Vec::new() };
+        charger.solve();
+        charger
+    }
+
+    /// Adds a bond and returns the updated charges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` or `j` is not a valid atom index.
+    pub fn add_bond(&mut self, i: usize, j: usize, order: f32) -> &[f64] {
+        let n = self.atoms.len();
+        assert!(i < n && j < n, "bond ({}, {}) out of range for {} atoms", i, j, n);
+        self.bonds.push(Bond::new(i, j, order));
+        self.reperceive(i, j);
+        &self.charges
+    }
+
+    /// Removes the first bond between `i` and `j` (in either direction).
+    /// Returns `false`, leaving everything unchanged, if there is no such bond.
+    pub fn remove_bond(&mut self, i: usize, j: usize) -> bool {
+        let Some(pos) = self.bonds.iter().position(|b| b.pair == (i, j) || b.pair == (j, i)) else {
+            return false;
+        };
+        self.bonds.remove(pos);
+        self.reperceive(i, j);
+        true
+    }
+
+    pub fn charges(&self) -> &[f64] {
+        &self.charges
+    }
+
+    pub fn atoms(&self) -> &[A] {
+        &self.atoms
+    }
+
+    pub fn bonds(&self) -> &[Bond] {
+        &self.bonds
+    }
+
+    fn reperceive(&mut self, i: usize, j: usize) {
+        let endpoints = if i == j { vec![i] } else { vec![i, j] };
+        let updated = self.solver.resolve_subset(&self.atoms, &self.bonds, &endpoints);
+        for (&index, params) in endpoints.iter().zip(updated) {
+            self.params[index] = params;
+        }
+        self.solve();
+    }
+
+    fn solve(&mut self) {
+        let topology = self.solver.topology_from_params(self.params.clone(), &self.atoms, &self.bonds);
+        self.charges = self.atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        self.solver.equalize_molecule(&mut self.charges, &topology);
+        self.solver.finish(&self.atoms, &self.bonds, &topology, &mut self.charges);
+    }
+}
@@ -0,0 +1,38 @@
+//! Streaming reader for molecules stored as JSON Lines (requires the `serde` feature).
+//!
+//! Each non-blank line is one JSON object:
+//!
+//! ```json
+//! {"name": "water", "atoms": [{"atomic_number": 8, "formal_charge": 0.0}, {"atomic_number": 1}, {"atomic_number": 1}], "bonds": [{"pair": [0, 1], "order": 1.0}, {"pair": [0, 2], "order": 1.0}]}
+//! ```
+//!
+//! `name` is optional, `formal_charge` defaults to `0.0`, and bond `pair`
+//! entries are zero-based indices into `atoms`.
+
+use std::io::{self, BufRead};
+
+use serde::Deserialize;
+
+use crate::molecule::{Atom, Bond};
+
+/// One molecule record read from a JSON Lines stream.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ParsedMolecule {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub atoms: Vec<Atom>,
+    #[serde(default)]
+    pub bonds: Vec<Bond>,
+}
+
+/// Lazily parses one `ParsedMolecule` per non-blank line of `reader`.
+///
+/// Malformed lines yield an `io::ErrorKind::InvalidData` error; iteration can
+/// continue past them.
+pub fn read_jsonl<R: BufRead>(reader: R) -> impl Iterator<Item = io::Result<ParsedMolecule>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) if line.trim().is_empty() => None,
+        Ok(line) => Some(serde_json::from_str(&line).map_err(io::Error::from)),
+        Err(e) => Some(Err(e)),
+    })
+}
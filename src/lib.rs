@@ -1,9 +1,34 @@
 pub mod traits;
 pub mod parameters;
+pub mod model;
 pub mod solver;
+pub mod context;
+pub mod interactive;
+pub mod linearized;
+mod anderson;
+pub mod calibration;
+mod topology;
+pub mod error;
+pub mod analysis;
+pub mod molecule;
+pub mod electrostatics;
+#[cfg(feature = "serde")]
+pub mod jsonl;
+#[cfg(feature = "petgraph")]
+pub mod graph;
+#[cfg(feature = "simd")]
+pub mod simd;
+#[cfg(feature = "io")]
+pub mod mol2;
+#[cfg(feature = "io")]
+pub mod pqr;
 
-pub use traits::{GasteigerAtom, GasteigerBond};
-pub use solver::GasteigerSolver;
+pub use traits::{BondKind, BondOrder, GasteigerAtom, GasteigerBond, IdentifiedAtom, PartialChargeSink, PositionedAtom, ResolutionObserver};
+pub use molecule::{Atom, Bond, PackedBond};
+pub use model::{ElectronegativityModel, QuadraticModel};
+pub use context::{ChargeCache, SolverContext};
+pub use error::SolverError;
+pub use solver::{DEFAULT_DAMPING, DEFAULT_ITERATIONS, BondOrderThresholds, ChargeResult, ConvergenceNorm, DampingMode, DegreeNormalization, GasteigerSolver, HybridizationThresholds, OutputConvention, SigmaHole, SolveMode, UnknownElementPolicy};
 
 #[cfg(test)]
 mod tests {
@@ -260,4 +285,2554 @@ mod tests {
         let total_charge: f64 = charges.iter().sum();
         assert!((total_charge - 1.0).abs() < 1e-6);
     }
+
+    #[test]
+    fn test_fixed_point_charges() {
+        let atoms = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let fixed = solver.compute_charges_fixed(&atoms, &bonds);
+        let charges = solver.compute_charges(&atoms, &bonds);
+        println!("\n--- Water (H2O), milli-electrons ---");
+        println!("{:?}", fixed);
+        assert!(fixed[0] < 0);
+        assert_eq!(fixed[0], (charges[0] * 1000.0).round() as i32);
+        assert_eq!(fixed, solver.compute_charges_fixed(&atoms, &bonds));
+    }
+
+    #[test]
+    fn test_custom_linear_model() {
+        use crate::parameters::GasteigerParams;
+
+        struct LinearModel;
+
+        impl ElectronegativityModel for LinearModel {
+            fn electronegativity(&self, p: &GasteigerParams, q: f64) -> f64 {
+                p.a + p.b * q
+            }
+        }
+
+        let atoms = vec![
+            MockAtom { name: "H", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "F", element: 9, formal_charge: 0.0 },
+        ];
+        let bonds = vec![MockBond { pair: (0, 1), order: 1.0 }];
+
+        let mut solver = GasteigerSolver::with_model(LinearModel);
+        solver.iterations = 1;
+        let charges = solver.compute_charges(&atoms, &bonds);
+        println!("\n--- Hydrogen Fluoride (HF), linear model ---");
+        print_charges(&atoms, &charges);
+
+        // chi_H = 7.17, chi_F = 14.66, chi_H(+1) = 7.17 + 6.24
+        let dq = (14.66 - 7.17) / (7.17 + 6.24);
+        assert!((charges[0] - dq).abs() < 1e-12);
+        assert!((charges[1] + dq).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_charge_extrema() {
+        let atoms = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let charges = solver.compute_charges(&atoms, &bonds);
+        let (most_negative, most_positive) = analysis::extrema(&charges);
+        assert_eq!(most_negative, 0);
+        // Both hydrogens are equivalent; the tie resolves to the lowest index.
+        assert_eq!(most_positive, 1);
+    }
+
+    #[test]
+    fn test_solver_clone() {
+        let atoms = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let solver = GasteigerSolver { iterations: 8, damping: 0.4, ..Default::default() };
+        let cloned = solver.clone();
+        println!("{:?}", cloned);
+        assert_eq!(solver.compute_charges(&atoms, &bonds), cloned.compute_charges(&atoms, &bonds));
+    }
+
+    #[test]
+    fn test_adaptive_damping() {
+        // H-F overshoots on the first undamped step, so the sign of each
+        // atom's update flips on the following iteration.
+        let atoms = vec![
+            MockAtom { name: "H", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "F", element: 9, formal_charge: 0.0 },
+        ];
+        let bonds = vec![MockBond { pair: (0, 1), order: 1.0 }];
+
+        let geometric = GasteigerSolver { iterations: 100, tolerance: Some(1e-6), ..Default::default() };
+        let adaptive = GasteigerSolver { damping_mode: DampingMode::Adaptive, ..geometric.clone() };
+
+        let geo = geometric.compute_charges_with_report(&atoms, &bonds);
+        let ada = adaptive.compute_charges_with_report(&atoms, &bonds);
+        println!("\n--- Hydrogen Fluoride (HF), geometric vs adaptive ---");
+        println!("geometric: {} iterations, adaptive: {} iterations", geo.iterations, ada.iterations);
+        print_charges(&atoms, &ada.charges);
+        assert!(geo.converged && ada.converged);
+        assert!(ada.iterations < geo.iterations);
+        assert!(ada.charges.iter().sum::<f64>().abs() < 1e-6);
+        assert!(ada.charges[1] < 0.0);
+
+        // Existing test systems converge at least as fast.
+        let atoms_water = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds_water = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let geo = geometric.compute_charges_with_report(&atoms_water, &bonds_water);
+        let ada = adaptive.compute_charges_with_report(&atoms_water, &bonds_water);
+        assert!(ada.iterations <= geo.iterations);
+    }
+
+    #[test]
+    fn test_charge_sensitivity() {
+        let atoms = vec![
+            MockAtom { name: "N+", element: 7, formal_charge: 1.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H3", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H4", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+            MockBond { pair: (0, 3), order: 1.0 },
+            MockBond { pair: (0, 4), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let sensitivity = solver.charge_sensitivity(&atoms, &bonds, 0);
+        println!("\n--- Ammonium Ion (NH4+), dq/dQ(N) ---");
+        print_charges(&atoms, &sensitivity);
+        // Extra positive charge on nitrogen is partly pushed out to the hydrogens.
+        assert!(sensitivity[1..].iter().all(|&s| s > 0.0));
+        assert!((sensitivity.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_concat_molecules() {
+        let methane_atoms = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let methane_bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0), Bond::new(0, 3, 1.0), Bond::new(0, 4, 1.0)];
+        let water_atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let water_bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+
+        let (atoms, bonds) = molecule::concat_molecules(&methane_atoms, &methane_bonds, &water_atoms, &water_bonds);
+        assert_eq!(atoms.len(), 8);
+        let pairs: Vec<(usize, usize)> = bonds.iter().map(|b| b.pair).collect();
+        assert_eq!(pairs, vec![(0, 1), (0, 2), (0, 3), (0, 4), (5, 6), (5, 7)]);
+
+        let solver = GasteigerSolver::default();
+        let charges = solver.compute_charges(&atoms, &bonds);
+        assert!(charges[0..5].iter().sum::<f64>().abs() < 1e-6);
+        assert!(charges[5..8].iter().sum::<f64>().abs() < 1e-6);
+        assert!(charges[5] < 0.0);
+    }
+
+    #[test]
+    fn test_target_total_charge() {
+        // Methanol (CH3OH)
+        let atoms = vec![
+            MockAtom { name: "C", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H3", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "HO", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+            MockBond { pair: (0, 3), order: 1.0 },
+            MockBond { pair: (0, 4), order: 1.0 },
+            MockBond { pair: (1, 5), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let neutral = solver.compute_charges(&atoms, &bonds);
+        let charges = solver.compute_charges_with_total(&atoms, &bonds, 1.0);
+        println!("\n--- Methanol (CH3OH) forced to +1 ---");
+        print_charges(&atoms, &charges);
+        assert!((charges.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+        assert!(((charges[1] - charges[0]) - (neutral[1] - neutral[0])).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_iodine_sigma_hole() {
+        // Iodomethane (CH3I)
+        let atoms = vec![
+            MockAtom { name: "C", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "I", element: 53, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H3", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+            MockBond { pair: (0, 3), order: 1.0 },
+            MockBond { pair: (0, 4), order: 1.0 },
+        ];
+        let coords = [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.0, 2.14],
+            [1.03, 0.0, -0.36],
+            [-0.51, 0.89, -0.36],
+            [-0.51, -0.89, -0.36],
+        ];
+        let solver = GasteigerSolver::default();
+        let isotropic = solver.compute_charges(&atoms, &bonds);
+        let holes = solver.sigma_holes(&atoms, &bonds, &coords);
+        assert_eq!(holes.len(), 1);
+        let hole = holes[0];
+        println!("\n--- Iodomethane sigma hole: {:?}", hole);
+        assert_eq!(hole.atom, 1);
+        assert_eq!(hole.isotropic_charge, isotropic[1]);
+        assert!(hole.axial_charge > hole.isotropic_charge);
+        assert!((hole.direction[2] - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_solver_context_reuse() {
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+            MockBond { pair: (0, 3), order: 1.0 },
+            MockBond { pair: (0, 4), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let mut context = SolverContext::new(&solver);
+
+        for step in 0..10 {
+            let fc = step as f32 * 0.2 - 1.0;
+            let atoms = vec![
+                MockAtom { name: "N", element: 7, formal_charge: fc },
+                MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+                MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+                MockAtom { name: "H3", element: 1, formal_charge: 0.0 },
+                MockAtom { name: "H4", element: 1, formal_charge: 0.0 },
+            ];
+            assert_eq!(context.solve(&atoms, &bonds), solver.compute_charges(&atoms, &bonds));
+            assert!(context.is_cached());
+        }
+
+        // After a topology change the cache must be rebuilt.
+        context.invalidate();
+        assert!(!context.is_cached());
+        let atoms = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = &bonds[..2];
+        assert_eq!(context.solve(&atoms, bonds), solver.compute_charges(&atoms, bonds));
+    }
+
+    #[test]
+    fn test_charges_by_id() {
+        struct SerialAtom {
+            serial: u64,
+            element: usize,
+        }
+
+        impl GasteigerAtom for SerialAtom {
+            fn atomic_number(&self) -> usize { self.element }
+        }
+
+        impl IdentifiedAtom for SerialAtom {
+            fn id(&self) -> u64 { self.serial }
+        }
+
+        let atoms = vec![
+            SerialAtom { serial: 1017, element: 8 },
+            SerialAtom { serial: 1018, element: 1 },
+            SerialAtom { serial: 2045, element: 1 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let by_id = solver.compute_charges_by_id(&atoms, &bonds);
+        let charges = solver.compute_charges(&atoms, &bonds);
+
+        let mut keys: Vec<u64> = by_id.keys().copied().collect();
+        keys.sort();
+        assert_eq!(keys, vec![1017, 1018, 2045]);
+        assert_eq!(by_id[&1017], charges[0]);
+        assert_eq!(by_id[&2045], charges[2]);
+    }
+
+    #[test]
+    fn test_checked_formal_charge_range() {
+        let atoms = vec![
+            MockAtom { name: "Fe", element: 26, formal_charge: 1e30 },
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+        ];
+        let bonds = vec![MockBond { pair: (0, 1), order: 1.0 }];
+        let solver = GasteigerSolver::default();
+        assert_eq!(
+            solver.compute_charges_checked(&atoms, &bonds),
+            Err(SolverError::FormalChargeOutOfRange { index: 0, value: 1e30 })
+        );
+
+        let atoms_ok = vec![
+            MockAtom { name: "Mn", element: 25, formal_charge: 7.0 },
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+        ];
+        assert!(solver.compute_charges_checked(&atoms_ok, &bonds).is_ok());
+
+        let bad_bonds = vec![MockBond { pair: (0, 5), order: 1.0 }];
+        assert!(matches!(
+            solver.compute_charges_checked(&atoms_ok, &bad_bonds),
+            Err(SolverError::BondIndexOutOfRange { bond: 0, index: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_perception_warnings() {
+        // Dimethyl sulfone (CH3-SO2-CH3), heavy atoms only
+        let atoms = vec![
+            MockAtom { name: "S", element: 16, formal_charge: 0.0 },
+            MockAtom { name: "O1", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "O2", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "C1", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "C2", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "Pd", element: 46, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 2.0 },
+            MockBond { pair: (0, 2), order: 2.0 },
+            MockBond { pair: (0, 3), order: 1.0 },
+            MockBond { pair: (0, 4), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let warnings = solver.perception_warnings(&atoms, &bonds);
+        println!("\n--- Dimethyl Sulfone warnings: {:?}", warnings);
+        assert!(warnings.iter().any(|(i, msg)| *i == 0 && msg.contains("hypervalent")));
+        assert!(warnings.iter().any(|(i, _)| *i == 5));
+        assert!(warnings.iter().all(|(i, _)| *i == 0 || *i == 5));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_read_jsonl() {
+        let data = r#"{"name": "water", "atoms": [{"atomic_number": 8}, {"atomic_number": 1}, {"atomic_number": 1}], "bonds": [{"pair": [0, 1], "order": 1.0}, {"pair": [0, 2], "order": 1.0}]}
+
+{"name": "ammonium", "atoms": [{"atomic_number": 7, "formal_charge": 1.0}, {"atomic_number": 1}, {"atomic_number": 1}, {"atomic_number": 1}, {"atomic_number": 1}], "bonds": [{"pair": [0, 1], "order": 1.0}, {"pair": [0, 2], "order": 1.0}, {"pair": [0, 3], "order": 1.0}, {"pair": [0, 4], "order": 1.0}]}
+"#;
+        let solver = GasteigerSolver::default();
+        let molecules: Vec<_> = jsonl::read_jsonl(data.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(molecules.len(), 2);
+        assert_eq!(molecules[0].name.as_deref(), Some("water"));
+
+        let water = solver.compute_charges(&molecules[0].atoms, &molecules[0].bonds);
+        assert!(water[0] < 0.0);
+        let ammonium = solver.compute_charges(&molecules[1].atoms, &molecules[1].bonds);
+        assert!((ammonium.iter().sum::<f64>() - 1.0).abs() < 1e-6);
+
+        assert!(jsonl::read_jsonl("{not json}".as_bytes()).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_charge_frames() {
+        let atoms = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let frames = solver.compute_charges_frames(&atoms, &bonds, 2);
+        // Seed plus iterations 2, 4 and 6.
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0], vec![0.0; 3]);
+        assert_eq!(frames[3], solver.compute_charges(&atoms, &bonds));
+
+        // Iteration 6 is not a multiple of 4, so the final state is appended.
+        assert_eq!(solver.compute_charges_frames(&atoms, &bonds, 4).len(), 3);
+    }
+
+    #[test]
+    fn test_supported_elements() {
+        let supported = parameters::supported_elements();
+        for z in [1, 6, 7, 8, 9, 15, 16, 17, 35, 53] {
+            assert!(supported.contains(&z), "missing element {}", z);
+        }
+        assert!(!supported.contains(&46));
+        assert!(supported.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_hybridization_thresholds() {
+        use crate::parameters::Hybridization;
+
+        // Hydrogen-suppressed benzene: every carbon has two aromatic bonds.
+        let atoms: Vec<MockAtom> = (0..6).map(|_| MockAtom { name: "C", element: 6, formal_charge: 0.0 }).collect();
+        let bonds: Vec<MockBond> = (0..6).map(|i| MockBond { pair: (i, (i + 1) % 6), order: 1.5 }).collect();
+
+        let solver = GasteigerSolver::default();
+        assert!(solver.perceive_hybridizations(&atoms, &bonds).iter().all(|&h| h == Hybridization::Sp2));
+
+        // Only treat clearly double bonds as sp2 evidence for carbon.
+        let mut strict = GasteigerSolver::default();
+        strict.hybridization_thresholds.carbon.aromatic = 1.75;
+        assert!(strict.perceive_hybridizations(&atoms, &bonds).iter().all(|&h| h == Hybridization::Sp));
+
+        // Bond orders refine atoms the neighbor count alone gets wrong.
+        let co2 = vec![
+            MockAtom { name: "C", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "O1", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "O2", element: 8, formal_charge: 0.0 },
+        ];
+        let co2_bonds = vec![
+            MockBond { pair: (0, 1), order: 2.0 },
+            MockBond { pair: (0, 2), order: 2.0 },
+        ];
+        let nitro = vec![
+            MockAtom { name: "N", element: 7, formal_charge: 1.0 },
+            MockAtom { name: "O1", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "O2", element: 8, formal_charge: -1.0 },
+            MockAtom { name: "C", element: 6, formal_charge: 0.0 },
+        ];
+        let nitro_bonds = vec![
+            MockBond { pair: (0, 1), order: 2.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+            MockBond { pair: (0, 3), order: 1.0 },
+        ];
+        assert_eq!(solver.perceive_hybridizations(&co2, &co2_bonds)[0], Hybridization::Sp);
+        assert_eq!(solver.perceive_hybridizations(&nitro, &nitro_bonds)[0], Hybridization::Sp2);
+    }
+
+    #[test]
+    fn test_polyethylene_parameter_memoization() {
+        use crate::parameters::get_params;
+
+        // H-(CH2)n-H
+        let n_carbons = 2000;
+        let mut atoms = Vec::new();
+        let mut bonds = Vec::new();
+        for c in 0..n_carbons {
+            atoms.push(Atom::new(6, 0.0));
+            if c > 0 {
+                bonds.push(Bond::new(3 * (c - 1), 3 * c, 1.0));
+            }
+            atoms.push(Atom::new(1, 0.0));
+            atoms.push(Atom::new(1, 0.0));
+            bonds.push(Bond::new(3 * c, 3 * c + 1, 1.0));
+            bonds.push(Bond::new(3 * c, 3 * c + 2, 1.0));
+        }
+        atoms.push(Atom::new(1, 0.0));
+        bonds.push(Bond::new(0, atoms.len() - 1, 1.0));
+        atoms.push(Atom::new(1, 0.0));
+        bonds.push(Bond::new(3 * (n_carbons - 1), atoms.len() - 1, 1.0));
+
+        let solver = GasteigerSolver::default();
+        let memoized = solver.resolve_params(&atoms, &bonds);
+        let direct: Vec<_> = solver
+            .perceive_hybridizations(&atoms, &bonds)
+            .iter()
+            .zip(atoms.iter())
+            .map(|(&h, a)| get_params(a.atomic_number, h))
+            .collect();
+        assert_eq!(memoized.len(), direct.len());
+        for (m, d) in memoized.iter().zip(direct.iter()) {
+            let (m, d) = (m.unwrap(), d.unwrap());
+            assert_eq!((m.a, m.b, m.c), (d.a, d.b, d.c));
+        }
+
+        // Memoized resolution gives exactly the charges of a per-atom lookup.
+        let charges = solver.compute_charges(&atoms, &bonds);
+        let hybridizations = solver.perceive_hybridizations(&atoms, &bonds);
+        assert_eq!(charges, solver.compute_charges_with_hybridizations(&atoms, &bonds, &hybridizations).unwrap());
+        assert!(charges.iter().sum::<f64>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_resonance_averaging() {
+        // Acetate (CH3COO-): O1 and O2 swap between C=O and C-O-.
+        let acetate = |charged: usize| {
+            let atoms = vec![
+                MockAtom { name: "C_me", element: 6, formal_charge: 0.0 },
+                MockAtom { name: "C_co", element: 6, formal_charge: 0.0 },
+                MockAtom { name: "O1", element: 8, formal_charge: if charged == 2 { -1.0 } else { 0.0 } },
+                MockAtom { name: "O2", element: 8, formal_charge: if charged == 3 { -1.0 } else { 0.0 } },
+                MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+                MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+                MockAtom { name: "H3", element: 1, formal_charge: 0.0 },
+            ];
+            let bonds = vec![
+                MockBond { pair: (0, 1), order: 1.0 },
+                MockBond { pair: (1, 2), order: if charged == 2 { 1.0 } else { 2.0 } },
+                MockBond { pair: (1, 3), order: if charged == 3 { 1.0 } else { 2.0 } },
+                MockBond { pair: (0, 4), order: 1.0 },
+                MockBond { pair: (0, 5), order: 1.0 },
+                MockBond { pair: (0, 6), order: 1.0 },
+            ];
+            (atoms, bonds)
+        };
+        let (atoms_a, bonds_a) = acetate(3);
+        let (atoms_b, bonds_b) = acetate(2);
+
+        let solver = GasteigerSolver::default();
+        let single = solver.compute_charges(&atoms_a, &bonds_a);
+        assert!((single[2] - single[3]).abs() > 1e-3);
+
+        let charges = solver
+            .compute_charges_resonance(&[(&atoms_a[..], &bonds_a[..]), (&atoms_b[..], &bonds_b[..])])
+            .unwrap();
+        println!("\n--- Acetate (CH3COO-), resonance averaged ---");
+        print_charges(&atoms_a, &charges);
+        assert!((charges[2] - charges[3]).abs() < 1e-12);
+        assert!((charges.iter().sum::<f64>() + 1.0).abs() < 1e-6);
+
+        let mismatch = solver.compute_charges_resonance(&[(&atoms_a[..], &bonds_a[..]), (&atoms_b[..3], &bonds_b[..])]);
+        assert_eq!(mismatch, Err(SolverError::AtomCountMismatch { expected: 7, found: 3 }));
+    }
+
+    #[test]
+    fn test_blended_aromatic_parameters() {
+        use crate::parameters::{get_params, GasteigerParams, Hybridization};
+
+        let sp3 = get_params(6, Hybridization::Sp3).unwrap();
+        let sp2 = get_params(6, Hybridization::Sp2).unwrap();
+        let half = sp3.lerp(&sp2, 0.5);
+        assert_eq!(sp3.lerp(&sp2, 0.0), sp3);
+        assert_eq!(sp3.lerp(&sp2, 1.0), sp2);
+
+        let mut atoms = Vec::new();
+        for _ in 0..6 { atoms.push(MockAtom { name: "C", element: 6, formal_charge: 0.0 }); }
+        for _ in 0..6 { atoms.push(MockAtom { name: "H", element: 1, formal_charge: 0.0 }); }
+        let mut bonds = Vec::new();
+        for i in 0..6 {
+            bonds.push(MockBond { pair: (i, (i + 1) % 6), order: 1.5 });
+            bonds.push(MockBond { pair: (i, i + 6), order: 1.0 });
+        }
+
+        let solver = GasteigerSolver { blend_fractional_orders: true, ..Default::default() };
+        let params = solver.resolve_params(&atoms, &bonds);
+        let carbon: GasteigerParams = params[0].unwrap();
+        assert!((carbon.a - half.a).abs() < 1e-12);
+        assert!((carbon.b - half.b).abs() < 1e-12);
+        assert!((carbon.c - half.c).abs() < 1e-12);
+        // Hydrogens only carry single bonds and are unaffected.
+        assert_eq!(params[6], get_params(1, Hybridization::Default));
+
+        let default_params = GasteigerSolver::default().resolve_params(&atoms, &bonds);
+        assert_eq!(default_params[0], Some(sp2));
+    }
+
+    #[test]
+    fn test_no_bonds() {
+        let solver = GasteigerSolver::default();
+        let neon = vec![MockAtom { name: "Ne", element: 10, formal_charge: 0.0 }];
+        let result = solver.compute_charges_with_report(&neon, &[] as &[MockBond]);
+        assert_eq!(result.charges, vec![0.0]);
+        assert_eq!(result.iterations, 0);
+
+        let cation = vec![MockAtom { name: "Na+", element: 11, formal_charge: 1.0 }];
+        assert_eq!(solver.compute_charges(&cation, &[] as &[MockBond]), vec![1.0]);
+
+        let empty: Vec<MockAtom> = Vec::new();
+        assert!(solver.compute_charges(&empty, &[] as &[MockBond]).is_empty());
+    }
+
+    #[test]
+    fn test_total_absolute_charge() {
+        let ring = |substituent: usize| {
+            let mut atoms = Vec::new();
+            for _ in 0..6 { atoms.push(Atom::new(6, 0.0)); }
+            for _ in 0..6 { atoms.push(Atom::new(substituent, 0.0)); }
+            let mut bonds = Vec::new();
+            for i in 0..6 {
+                bonds.push(Bond::new(i, (i + 1) % 6, 1.5));
+                bonds.push(Bond::new(i, i + 6, 1.0));
+            }
+            (atoms, bonds)
+        };
+        let solver = GasteigerSolver::default();
+        let (benzene, benzene_bonds) = ring(1);
+        let (hexafluoro, hexafluoro_bonds) = ring(9);
+        let polarity_h = analysis::total_absolute_charge(&solver.compute_charges(&benzene, &benzene_bonds));
+        let polarity_f = analysis::total_absolute_charge(&solver.compute_charges(&hexafluoro, &hexafluoro_bonds));
+        println!("\n--- Sum |q|: benzene {:.4}, hexafluorobenzene {:.4}", polarity_h, polarity_f);
+        assert!(polarity_f > 2.0 * polarity_h);
+        assert_eq!(analysis::total_absolute_charge(&[0.5, -0.25, -0.25]), 1.0);
+    }
+
+    #[test]
+    fn test_unknown_element_policies() {
+        use crate::parameters::GasteigerParams;
+
+        let atoms = vec![
+            MockAtom { name: "C", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "Pd", element: 46, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+
+        let spectator = GasteigerSolver::default();
+        assert_eq!(spectator.unknown_elements, UnknownElementPolicy::Spectator);
+        let charges = spectator.compute_charges_checked(&atoms, &bonds).unwrap();
+        assert!(charges[2].abs() < 1e-10);
+
+        let strict = GasteigerSolver { unknown_elements: UnknownElementPolicy::Error, ..Default::default() };
+        assert_eq!(
+            strict.compute_charges_checked(&atoms, &bonds),
+            Err(SolverError::UnknownElement { index: 2, atomic_number: 46 })
+        );
+        assert_eq!(strict.compute_charges(&atoms, &bonds), charges);
+
+        let generic = GasteigerParams { a: 6.0, b: 6.0, c: 0.0 };
+        let fallback = GasteigerSolver { unknown_elements: UnknownElementPolicy::GenericFallback(generic), ..Default::default() };
+        let charges = fallback.compute_charges_checked(&atoms, &bonds).unwrap();
+        println!("\n--- C-H + C-Pd with generic fallback ---");
+        print_charges(&atoms, &charges);
+        assert!(charges[2] > 0.0);
+        assert!(charges.iter().sum::<f64>().abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_resolution_observer() {
+        use crate::parameters::{get_params, GasteigerParams, Hybridization};
+
+        #[derive(Default)]
+        struct Recorder {
+            events: Vec<(usize, usize, Hybridization, Option<GasteigerParams>)>,
+        }
+
+        impl ResolutionObserver for Recorder {
+            fn on_atom_resolved(&mut self, index: usize, z: usize, guessed: Hybridization, resolved: Option<&GasteigerParams>) {
+                self.events.push((index, z, guessed, resolved.copied()));
+            }
+        }
+
+        let atoms = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let solver = GasteigerSolver::default();
+        let mut recorder = Recorder::default();
+        let charges = solver.compute_charges_observed(&atoms, &bonds, &mut recorder);
+
+        assert_eq!(charges, solver.compute_charges(&atoms, &bonds));
+        assert_eq!(recorder.events.len(), 3);
+        assert_eq!(recorder.events[0], (0, 8, Hybridization::Sp3, get_params(8, Hybridization::Sp3)));
+    }
+
+    #[test]
+    fn test_reference_comparison() {
+        let atoms = vec![
+            MockAtom { name: "O", element: 8, formal_charge: 0.0 },
+            MockAtom { name: "H1", element: 1, formal_charge: 0.0 },
+            MockAtom { name: "H2", element: 1, formal_charge: 0.0 },
+        ];
+        let bonds = vec![
+            MockBond { pair: (0, 1), order: 1.0 },
+            MockBond { pair: (0, 2), order: 1.0 },
+        ];
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+        assert_eq!(analysis::rmsd_to_reference(&charges, &charges), Ok(0.0));
+        assert_eq!(analysis::max_abs_error(&charges, &charges), Ok(0.0));
+
+        let perturbed: Vec<f64> = charges.iter().zip([0.03, 0.0, -0.03]).map(|(q, d)| q + d).collect();
+        let rmsd = analysis::rmsd_to_reference(&charges, &perturbed).unwrap();
+        assert!((rmsd - (0.0018f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!((analysis::max_abs_error(&charges, &perturbed).unwrap() - 0.03).abs() < 1e-12);
+
+        assert_eq!(
+            analysis::rmsd_to_reference(&charges, &perturbed[..2]),
+            Err(SolverError::AtomCountMismatch { expected: 2, found: 3 })
+        );
+    }
+
+    #[test]
+    fn test_element_default_hybridization() {
+        use crate::parameters::{GasteigerParams, Hybridization};
+
+        struct Guesses(Vec<Hybridization>);
+
+        impl ResolutionObserver for Guesses {
+            fn on_atom_resolved(&mut self, _: usize, _: usize, guessed: Hybridization, _: Option<&GasteigerParams>) {
+                self.0.push(guessed);
+            }
+        }
+
+        // Tetramethyltin core: Sn bonded to four carbons.
+        let atoms = vec![
+            MockAtom { name: "Sn", element: 50, formal_charge: 0.0 },
+            MockAtom { name: "C1", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "C2", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "C3", element: 6, formal_charge: 0.0 },
+            MockAtom { name: "C4", element: 6, formal_charge: 0.0 },
+        ];
+        let bonds: Vec<MockBond> = (1..5).map(|i| MockBond { pair: (0, i), order: 1.0 }).collect();
+
+        let mut guesses = Guesses(Vec::new());
+        GasteigerSolver::default().compute_charges_observed(&atoms, &bonds, &mut guesses);
+        assert_eq!(guesses.0[0], Hybridization::Default);
+
+        let mut solver = GasteigerSolver::default();
+        solver.element_hybridizations.insert(50, Hybridization::Sp3);
+        let mut guesses = Guesses(Vec::new());
+        solver.compute_charges_observed(&atoms, &bonds, &mut guesses);
+        assert_eq!(guesses.0[0], Hybridization::Sp3);
+    }
+
+    #[test]
+    fn test_is_fully_parameterized() {
+        let solver = GasteigerSolver::default();
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let methane_bonds: Vec<Bond> = (1..5).map(|i| Bond::new(0, i, 1.0)).collect();
+        assert!(solver.is_fully_parameterized(&methane, &methane_bonds));
+
+        let palladium = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(46, 0.0)];
+        let palladium_bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        assert!(!solver.is_fully_parameterized(&palladium, &palladium_bonds));
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn test_assign_charges_to_graph() {
+        use petgraph::graph::UnGraph;
+
+        struct GraphAtom {
+            element: usize,
+            charge: f64,
+        }
+
+        impl GasteigerAtom for GraphAtom {
+            fn atomic_number(&self) -> usize { self.element }
+        }
+
+        impl PartialChargeSink for GraphAtom {
+            fn set_partial_charge(&mut self, charge: f64) { self.charge = charge; }
+        }
+
+        let mut graph: UnGraph<GraphAtom, f32> = UnGraph::new_undirected();
+        let o = graph.add_node(GraphAtom { element: 8, charge: 0.0 });
+        let h1 = graph.add_node(GraphAtom { element: 1, charge: 0.0 });
+        let h2 = graph.add_node(GraphAtom { element: 1, charge: 0.0 });
+        graph.add_edge(o, h1, 1.0);
+        graph.add_edge(o, h2, 1.0);
+
+        let solver = GasteigerSolver::default();
+        solver.assign_charges_to_graph(&mut graph);
+
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let expected = solver.compute_charges(&atoms, &bonds);
+        for (node, q) in graph.node_weights().zip(expected) {
+            assert_eq!(node.charge, q);
+        }
+    }
+
+    #[test]
+    fn test_linearized_mode() {
+        let water = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let water_bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let nitrile = vec![
+            Atom::new(6, 0.0), Atom::new(6, 0.0), Atom::new(7, 0.0),
+            Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0),
+        ];
+        let nitrile_bonds = vec![
+            Bond::new(0, 1, 1.0), Bond::new(1, 2, 3.0),
+            Bond::new(0, 3, 1.0), Bond::new(0, 4, 1.0), Bond::new(0, 5, 1.0),
+        ];
+
+        // Undamped iteration run to convergence targets the same fixed point.
+        let iterative = GasteigerSolver {
+            iterations: 100_000,
+            damping: 1.0,
+            damping_mode: DampingMode::Adaptive,
+            tolerance: Some(1e-12),
+            ..Default::default()
+        };
+        let linearized = GasteigerSolver { mode: SolveMode::Linearized, ..Default::default() };
+
+        for (atoms, bonds) in [(&water, &water_bonds), (&nitrile, &nitrile_bonds)] {
+            let reference = iterative.compute_charges_with_report(atoms, bonds);
+            let result = linearized.compute_charges_with_report(atoms, bonds);
+            println!("\nlinearized {:?} in {} steps, iterative in {}", result.charges, result.iterations, reference.iterations);
+            assert!(reference.converged && result.converged);
+            assert!(result.iterations < reference.iterations);
+            assert!(analysis::max_abs_error(&result.charges, &reference.charges).unwrap() < 1e-6);
+            assert!(result.charges.iter().sum::<f64>().abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_periodic_bonds_reported() {
+        struct CellBond {
+            pair: (usize, usize),
+            periodic: bool,
+        }
+
+        impl GasteigerBond for CellBond {
+            fn atom_indices(&self) -> (usize, usize) { self.pair }
+            fn bond_order(&self) -> f32 { 1.0 }
+            fn is_periodic_image(&self) -> bool { self.periodic }
+        }
+
+        // A polyethylene-like repeat unit whose C-C bond wraps into the next cell.
+        let atoms = vec![Atom::new(6, 0.0), Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let pairs = [(0, 1), (1, 0), (0, 2), (0, 3), (1, 4), (1, 5)];
+        let flagged: Vec<CellBond> = pairs.iter().enumerate().map(|(k, &pair)| CellBond { pair, periodic: k == 1 }).collect();
+        let plain: Vec<CellBond> = pairs.iter().map(|&pair| CellBond { pair, periodic: false }).collect();
+
+        let solver = GasteigerSolver::default();
+        let result = solver.compute_charges_with_report(&atoms, &flagged);
+        assert_eq!(result.periodic_bonds, vec![1]);
+        assert_eq!(result.charges, solver.compute_charges(&atoms, &plain));
+        assert!(solver.compute_charges_with_report(&atoms, &plain).periodic_bonds.is_empty());
+    }
+
+    #[test]
+    fn test_scale_charges_to_dipole() {
+        let norm = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        let coords = [[0.0, 0.0, 0.0], [0.757, 0.586, 0.0], [-0.757, 0.586, 0.0]];
+        let mut charges = vec![-0.4, 0.2, 0.2];
+        let before = electrostatics::dipole_moment(&charges, &coords).unwrap();
+
+        let target = [0.0, 0.5, 0.0];
+        let scale = electrostatics::scale_charges_to_dipole(&mut charges, &coords, target).unwrap();
+        let after = electrostatics::dipole_moment(&charges, &coords).unwrap();
+        println!("\n--- Dipole scaling: |mu| {:.4} -> {:.4} (s = {:.4})", norm(before), norm(after), scale);
+
+        assert!(scale > 1.0);
+        assert!(norm(after) > norm(before));
+        assert!((after[1] - 0.5).abs() < 1e-9);
+        assert!(charges.iter().sum::<f64>().abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_inconsistent_aromatic_ring() {
+        let benzene = |mislabeled: Option<usize>| {
+            let mut atoms = Vec::new();
+            for _ in 0..6 { atoms.push(Atom::new(6, 0.0)); }
+            for _ in 0..6 { atoms.push(Atom::new(1, 0.0)); }
+            let mut bonds = Vec::new();
+            for i in 0..6 {
+                let order = if mislabeled == Some(i) { 1.0 } else { 1.5 };
+                bonds.push(Bond::new(i, (i + 1) % 6, order));
+                bonds.push(Bond::new(i, i + 6, 1.0));
+            }
+            (atoms, bonds)
+        };
+        let solver = GasteigerSolver::default();
+
+        let (atoms, bonds) = benzene(None);
+        assert!(solver.validate(&atoms, &bonds).is_ok());
+
+        let (atoms, bonds) = benzene(Some(3));
+        match solver.validate(&atoms, &bonds) {
+            Err(SolverError::InconsistentAromaticRing { atoms: mut ring }) => {
+                ring.sort();
+                assert_eq!(ring, vec![0, 1, 2, 3, 4, 5]);
+            }
+            other => panic!("expected an inconsistent ring, got {:?}", other),
+        }
+
+        // Kekulé benzene and an aromatic ring fused to a saturated one are fine.
+        let kekule: Vec<Bond> = bonds
+            .iter()
+            .enumerate()
+            .map(|(k, b)| if b.order == 1.0 { *b } else { Bond::new(b.pair.0, b.pair.1, if k % 4 == 0 { 2.0 } else { 1.0 }) })
+            .collect();
+        assert!(solver.validate(&atoms, &kekule).is_ok());
+
+        let (mut atoms, mut bonds) = benzene(None);
+        bonds.retain(|b| b.pair != (0, 6) && b.pair != (1, 7));
+        atoms.truncate(6);
+        bonds.retain(|b| b.pair.1 < 6);
+        for _ in 0..4 { atoms.push(Atom::new(6, 0.0)); }
+        for (i, j) in [(0, 6), (6, 7), (7, 8), (8, 9), (9, 1)] {
+            bonds.push(Bond::new(i, j, 1.0));
+        }
+        assert!(solver.validate(&atoms, &bonds).is_ok());
+    }
+
+    #[test]
+    fn test_packed_bonds() {
+        let packed = PackedBond::pack(PackedBond::MAX_INDEX, 42, 1.5).unwrap();
+        assert_eq!(packed.unpack(), (PackedBond::MAX_INDEX, 42, 1.5));
+        assert!(PackedBond::pack(PackedBond::MAX_INDEX + 1, 0, 1.0).is_none());
+        assert!(PackedBond::pack(0, 1, 1.1).is_none());
+
+        // Ethene: C=C with two H on each carbon.
+        let atoms = vec![
+            Atom::new(6, 0.0), Atom::new(6, 0.0),
+            Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0),
+        ];
+        let bonds = vec![
+            Bond::new(0, 1, 2.0), Bond::new(0, 2, 1.0), Bond::new(0, 3, 1.0),
+            Bond::new(1, 4, 1.0), Bond::new(1, 5, 1.0),
+        ];
+        let packed: Vec<PackedBond> = bonds.iter().map(|b| PackedBond::pack(b.pair.0, b.pair.1, b.order).unwrap()).collect();
+
+        let solver = GasteigerSolver::default();
+        let expected = solver.compute_charges(&atoms, &bonds);
+        let charges = solver.compute_charges(&atoms, &packed);
+        assert_eq!(charges, expected);
+    }
+
+    #[test]
+    fn test_initial_electronegativities() {
+        // Formaldehyde: C=O with two H on carbon, plus a Pd spectator.
+        let atoms = vec![Atom::new(6, 0.0), Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(46, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 2.0), Bond::new(0, 2, 1.0), Bond::new(0, 3, 1.0)];
+
+        let chi = GasteigerSolver::default().initial_electronegativities(&atoms, &bonds);
+        assert!(chi[1] > chi[0], "O chi {} should exceed C chi {}", chi[1], chi[0]);
+        assert!((chi[0] - 8.79).abs() < 1e-9, "carbon should use Sp2 a");
+        assert!(chi[4].is_nan());
+    }
+
+    #[test]
+    fn test_round_charges() {
+        let mut ties = vec![0.0125, -0.0135, 0.1234];
+        analysis::round_charges(&mut ties, 3);
+        assert_eq!(ties, vec![0.012, -0.014, 0.123]);
+
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let solver = GasteigerSolver { output_decimals: Some(3), ..Default::default() };
+        let rounded = solver.compute_charges(&atoms, &bonds);
+
+        let mut again = rounded.clone();
+        analysis::round_charges(&mut again, 3);
+        assert_eq!(again, rounded, "rounding must be idempotent");
+
+        let raw = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+        for (r, q) in rounded.iter().zip(&raw) {
+            assert!((r - q).abs() <= 5e-4 + 1e-12);
+        }
+        assert!(rounded.iter().sum::<f64>().abs() <= 1.5e-3);
+    }
+
+    #[test]
+    fn test_param_provider() {
+        use crate::parameters::{GasteigerParams, Hybridization, ParamProvider};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+
+        // Identical parameters everywhere: nothing drives a transfer.
+        let constant = |_: usize, _: Hybridization| Some(GasteigerParams { a: 7.0, b: 9.0, c: 1.0 });
+        let solver = GasteigerSolver { param_provider: Arc::new(constant), ..Default::default() };
+        let charges = solver.compute_charges(&atoms, &bonds);
+        assert!(charges.iter().all(|q| q.abs() < 1e-12));
+
+        // A map provider reproducing the built-in entries gives the built-in result.
+        let mut map = HashMap::new();
+        for (z, h) in [(8, Hybridization::Sp3), (1, Hybridization::Default)] {
+            map.insert((z, h), crate::parameters::BuiltinParams.params(z, h).unwrap());
+        }
+        let solver = GasteigerSolver { param_provider: Arc::new(map), ..Default::default() };
+        assert_eq!(solver.compute_charges(&atoms, &bonds), GasteigerSolver::default().compute_charges(&atoms, &bonds));
+    }
+
+    #[test]
+    fn test_orphaned_atoms() {
+        // Pd-C-Pd bridge next to an unrelated water molecule.
+        let atoms = vec![
+            Atom::new(46, 0.0), Atom::new(6, 0.0), Atom::new(46, 0.0),
+            Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0),
+        ];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(1, 2, 1.0), Bond::new(3, 4, 1.0), Bond::new(3, 5, 1.0)];
+
+        let result = GasteigerSolver::default().compute_charges_with_report(&atoms, &bonds);
+        assert_eq!(result.orphaned_atoms, vec![1]);
+        assert_eq!(result.charges[1], 0.0);
+        assert!(result.charges[3] < 0.0, "water is still equalized");
+    }
+
+    #[test]
+    fn test_explicit_hybridizations() {
+        use crate::parameters::Hybridization;
+
+        let atoms = vec![
+            Atom::new(6, 0.0), Atom::new(6, 0.0),
+            Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0),
+        ];
+        let bonds = vec![
+            Bond::new(0, 1, 2.0), Bond::new(0, 2, 1.0), Bond::new(0, 3, 1.0),
+            Bond::new(1, 4, 1.0), Bond::new(1, 5, 1.0),
+        ];
+        let solver = GasteigerSolver::default();
+        let mut hybs = vec![Hybridization::Default; 6];
+        hybs[0] = Hybridization::Sp2;
+        hybs[1] = Hybridization::Sp2;
+
+        let explicit = solver.compute_charges_with_hybridizations(&atoms, &bonds, &hybs).unwrap();
+        assert_eq!(explicit, solver.compute_charges(&atoms, &bonds));
+
+        assert!(matches!(
+            solver.compute_charges_with_hybridizations(&atoms, &bonds, &hybs[..2]),
+            Err(SolverError::AtomCountMismatch { expected: 6, found: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_compare_tautomers() {
+        // Formamide and formimidic acid: C, O, N, H(C), H(N), mobile H.
+        let atoms = vec![
+            Atom::new(6, 0.0), Atom::new(8, 0.0), Atom::new(7, 0.0),
+            Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0),
+        ];
+        let amide = vec![
+            Bond::new(0, 1, 2.0), Bond::new(0, 2, 1.0), Bond::new(0, 3, 1.0),
+            Bond::new(2, 4, 1.0), Bond::new(2, 5, 1.0),
+        ];
+        let imidic_acid = vec![
+            Bond::new(0, 1, 1.0), Bond::new(0, 2, 2.0), Bond::new(0, 3, 1.0),
+            Bond::new(2, 4, 1.0), Bond::new(1, 5, 1.0),
+        ];
+
+        let forms: [(&[Atom], &[Bond]); 2] = [(&atoms, &amide), (&atoms, &imidic_acid)];
+        let results = GasteigerSolver::default().compare_tautomers(&forms);
+        assert_eq!(results.len(), 2);
+        assert!(
+            results[1].charges[5] > results[0].charges[5],
+            "the mobile H should be more positive on O ({}) than on N ({})",
+            results[1].charges[5],
+            results[0].charges[5]
+        );
+        for result in &results {
+            assert!(result.charges.iter().sum::<f64>().abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_assert_charges_approx_eq() {
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+        let solver = GasteigerSolver::default();
+
+        let first = solver.compute_charges(&methane, &bonds);
+        let second = solver.compute_charges(&methane, &bonds);
+        assert_charges_approx_eq!(first, second);
+        assert_charges_approx_eq!(first, second, 1e-12);
+
+        assert!(!analysis::charges_approx_eq(&first, &second[..4], 1.0));
+        assert!(!analysis::charges_approx_eq(&[f64::NAN], &[f64::NAN], 1.0));
+    }
+
+    #[test]
+    fn test_split_components() {
+        // Methane (small transfers, converges early) next to HF (large transfers).
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let methane_bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+        let hf = vec![Atom::new(9, 0.0), Atom::new(1, 0.0)];
+        let hf_bonds = vec![Bond::new(0, 1, 1.0)];
+        let (atoms, bonds) = molecule::concat_molecules(&methane, &methane_bonds, &hf, &hf_bonds);
+
+        let split = GasteigerSolver { tolerance: Some(1e-4), iterations: 100, split_components: true, ..Default::default() };
+        let joint = GasteigerSolver { split_components: false, ..split.clone() };
+
+        let result = split.compute_charges_with_report(&atoms, &bonds);
+        assert!(result.converged);
+        assert!(result.charges[..5].iter().sum::<f64>().abs() < 1e-12);
+        assert!(result.charges[5..].iter().sum::<f64>().abs() < 1e-12);
+
+        // Each fragment matches its isolated solve exactly, which the joint solve does not.
+        assert_eq!(result.charges[..5], split.compute_charges(&methane, &methane_bonds)[..]);
+        assert_eq!(result.charges[5..], split.compute_charges(&hf, &hf_bonds)[..]);
+        let joint_charges = joint.compute_charges(&atoms, &bonds);
+        assert_ne!(joint_charges[..5], split.compute_charges(&methane, &methane_bonds)[..]);
+
+        // Every other entry point that returns finished charges splits too.
+        assert_eq!(split.compute_charges_observed(&atoms, &bonds, &mut ()), result.charges);
+        let hybridizations = split.perceive_hybridizations(&atoms, &bonds);
+        assert_eq!(split.compute_charges_with_hybridizations(&atoms, &bonds, &hybridizations).unwrap(), result.charges);
+        assert_eq!(SolverContext::new(&split).solve(&atoms, &bonds), result.charges);
+        let mut charger = crate::interactive::InteractiveCharger::new(&split, atoms.clone());
+        for bond in &bonds {
+            charger.add_bond(bond.pair.0, bond.pair.1, bond.order);
+        }
+        assert_eq!(charger.charges(), &result.charges[..]);
+    }
+
+    #[test]
+    fn test_bonds_from_coordinates() {
+        use crate::molecule::{bonds_from_coordinates, BondCutoffs};
+
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let coords = [[0.0, 0.0, 0.1173], [0.0, 0.7572, -0.4692], [0.0, -0.7572, -0.4692]];
+
+        let bonds = bonds_from_coordinates(&atoms, &coords, &BondCutoffs::default()).unwrap();
+        assert_eq!(bonds, vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)]);
+
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+        assert!(charges[0] < 0.0 && charges[1] > 0.0);
+
+        assert!(bonds_from_coordinates(&atoms, &coords[..2], &BondCutoffs::default()).is_err());
+    }
+
+    #[test]
+    fn test_damping_sequence() {
+        let sequence = GasteigerSolver::default().damping_sequence();
+        assert_eq!(sequence, vec![1.0, 0.5, 0.25, 0.125, 0.0625, 0.03125]);
+
+        let linearized = GasteigerSolver { mode: SolveMode::Linearized, ..Default::default() };
+        assert!(linearized.damping_sequence().is_empty());
+    }
+
+    #[test]
+    fn test_virtual_sites() {
+        struct Site { atomic_number: usize, virtual_site: bool }
+        impl GasteigerAtom for Site {
+            fn atomic_number(&self) -> usize { self.atomic_number }
+            fn is_virtual_site(&self) -> bool { self.virtual_site }
+        }
+
+        // TIP4P-like water: O, H, H and an M site bonded to the oxygen.
+        let atoms = vec![
+            Site { atomic_number: 8, virtual_site: false },
+            Site { atomic_number: 1, virtual_site: false },
+            Site { atomic_number: 1, virtual_site: false },
+            Site { atomic_number: 0, virtual_site: true },
+        ];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0), Bond::new(0, 3, 1.0)];
+        let solver = GasteigerSolver::default();
+        let result = solver.compute_charges_with_report(&atoms, &bonds);
+
+        let water = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let plain = solver.compute_charges(&water, &bonds[..2]);
+
+        assert_eq!(result.virtual_sites, vec![3]);
+        assert!(result.charges[0].abs() < 1e-12, "oxygen nucleus should be neutral");
+        assert!((result.charges[3] - plain[0]).abs() < 1e-12, "M site carries the oxygen charge");
+        assert_eq!(result.charges[1..3], plain[1..3]);
+        assert!(result.charges.iter().sum::<f64>().abs() < 1e-9);
+        assert_eq!(solver.perceive_hybridizations(&atoms, &bonds)[0], solver.perceive_hybridizations(&water, &bonds[..2])[0]);
+    }
+
+    #[test]
+    fn test_charges_with_confidence() {
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let methane_bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+        // Methylpalladium: one of the carbon's neighbors is Pd.
+        let mut methyl_pd = methane.clone();
+        methyl_pd[4] = Atom::new(46, 0.0);
+
+        let solver = GasteigerSolver::default();
+        let reference = solver.compute_charges_with_confidence(&methane, &methane_bonds);
+        let with_pd = solver.compute_charges_with_confidence(&methyl_pd, &methane_bonds);
+
+        assert_eq!(reference[0].1, 1.0);
+        assert!(with_pd[0].1 < reference[0].1);
+        assert_eq!(with_pd[4].1, 0.0, "Pd itself is unparameterized");
+        assert_eq!(with_pd[0].0, solver.compute_charges(&methyl_pd, &methane_bonds)[0]);
+    }
+
+    #[test]
+    fn test_radical_fractional_formal_charges() {
+        // Nitric oxide modeled with the unpaired electron split as +0.5 / -0.5.
+        let atoms = vec![Atom::new(7, 0.5), Atom::new(8, -0.5)];
+        let bonds = vec![Bond::new(0, 1, 2.0)];
+        let solver = GasteigerSolver::default();
+        assert!(solver.validate(&atoms, &bonds).is_ok());
+
+        let charges = solver.compute_charges(&atoms, &bonds);
+        assert!(charges.iter().sum::<f64>().abs() < 1e-12);
+
+        // A half-integer net charge is conserved as well.
+        let cation = vec![Atom::new(7, 0.5), Atom::new(8, 0.0)];
+        let charges = solver.compute_charges(&cation, &bonds);
+        assert!((charges.iter().sum::<f64>() - 0.5).abs() < 1e-12);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn test_simd_matches_scalar_f32() {
+        // Octanoic acid, enough bonds for full SIMD chunks plus a remainder.
+        let mut atoms: Vec<Atom> = (0..8).map(|_| Atom::new(6, 0.0)).collect();
+        let mut bonds: Vec<Bond> = (1..8).map(|c| Bond::new(c - 1, c, 1.0)).collect();
+        atoms.push(Atom::new(8, 0.0));
+        atoms.push(Atom::new(8, 0.0));
+        bonds.push(Bond::new(7, 8, 2.0));
+        bonds.push(Bond::new(7, 9, 1.0));
+        for (c, hydrogens) in [(0, 3), (1, 2), (2, 2), (3, 2), (4, 2), (5, 2), (6, 2), (9, 1)] {
+            for _ in 0..hydrogens {
+                bonds.push(Bond::new(c, atoms.len(), 1.0));
+                atoms.push(Atom::new(1, 0.0));
+            }
+        }
+
+        let solver = GasteigerSolver::default();
+        let scalar = solver.compute_charges_f32(&atoms, &bonds);
+        let simd = solver.compute_charges_simd(&atoms, &bonds);
+        let reference = solver.compute_charges(&atoms, &bonds);
+        for ((s, v), r) in scalar.iter().zip(&simd).zip(&reference) {
+            assert!((s - v).abs() < 1e-5, "simd {} vs scalar {}", v, s);
+            assert!((*s as f64 - r).abs() < 1e-4, "f32 {} vs f64 {}", s, r);
+        }
+    }
+
+    #[test]
+    fn test_polar_hydrogen_params() {
+        let water = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let water_bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let methane_bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+
+        let generic = GasteigerSolver::default();
+        let polar = GasteigerSolver { polar_hydrogen_params: parameters::polar_hydrogen_params(), ..Default::default() };
+
+        let water_h = polar.compute_charges(&water, &water_bonds)[1];
+        let methane_h = polar.compute_charges(&methane, &methane_bonds)[1];
+        assert!(water_h > methane_h);
+        assert!(water_h > generic.compute_charges(&water, &water_bonds)[1], "O-H hydrogen gets more positive");
+        assert_eq!(polar.compute_charges(&methane, &methane_bonds), generic.compute_charges(&methane, &methane_bonds));
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        use crate::molecule::canonicalize;
+
+        // Ethene listed carbons-first and hydrogens-first.
+        let atoms_a = vec![
+            Atom::new(6, 0.0), Atom::new(6, 0.0),
+            Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0),
+        ];
+        let bonds_a = vec![
+            Bond::new(0, 1, 2.0), Bond::new(0, 2, 1.0), Bond::new(0, 3, 1.0),
+            Bond::new(1, 4, 1.0), Bond::new(1, 5, 1.0),
+        ];
+        let atoms_b = vec![
+            Atom::new(1, 0.0), Atom::new(6, 0.0), Atom::new(1, 0.0),
+            Atom::new(1, 0.0), Atom::new(6, 0.0), Atom::new(1, 0.0),
+        ];
+        let bonds_b = vec![
+            Bond::new(3, 4, 1.0), Bond::new(1, 0, 1.0), Bond::new(4, 1, 2.0),
+            Bond::new(5, 4, 1.0), Bond::new(2, 1, 1.0),
+        ];
+
+        let (perm_a, canon_atoms_a, canon_bonds_a) = canonicalize(&atoms_a, &bonds_a);
+        let (perm_b, canon_atoms_b, canon_bonds_b) = canonicalize(&atoms_b, &bonds_b);
+        assert_eq!(canon_atoms_a, canon_atoms_b);
+        assert_eq!(canon_bonds_a, canon_bonds_b);
+
+        let solver = GasteigerSolver::default();
+        let charges_a = solver.compute_charges(&canon_atoms_a, &canon_bonds_a);
+        let charges_b = solver.compute_charges(&canon_atoms_b, &canon_bonds_b);
+        assert_eq!(charges_a, charges_b);
+
+        // The permutation maps canonical atoms back to the input.
+        let original = solver.compute_charges(&atoms_b, &bonds_b);
+        for (k, &old) in perm_b.iter().enumerate() {
+            assert!((charges_b[k] - original[old]).abs() < 1e-12);
+        }
+        assert_eq!(perm_a.len(), 6);
+    }
+
+    #[test]
+    fn test_interactive_charger() {
+        use crate::interactive::InteractiveCharger;
+
+        let atoms = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let solver = GasteigerSolver::default();
+        let mut charger = InteractiveCharger::new(&solver, atoms.clone());
+        assert!(charger.charges().iter().all(|&q| q == 0.0));
+
+        for h in 1..5 {
+            let charges = charger.add_bond(0, h, 1.0);
+            assert!(charges.iter().sum::<f64>().abs() < 1e-12, "total drifted after bond {}", h);
+        }
+        let methane_bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+        assert_eq!(charger.charges(), &solver.compute_charges(&atoms, &methane_bonds)[..]);
+
+        assert!(charger.remove_bond(4, 0));
+        assert!(!charger.remove_bond(4, 0));
+        assert_eq!(charger.charges()[4], 0.0);
+        assert_eq!(charger.charges(), &solver.compute_charges(&atoms, &methane_bonds[..3])[..]);
+    }
+
+    #[test]
+    fn test_component_count() {
+        use crate::molecule::{component_count, concat_molecules};
+
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let methane_bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+        let water = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let water_bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let (atoms, bonds) = concat_molecules(&methane, &methane_bonds, &water, &water_bonds);
+        assert_eq!(component_count(&atoms, &bonds), 2);
+
+        let mut benzene: Vec<Atom> = (0..6).map(|_| Atom::new(6, 0.0)).collect();
+        benzene.extend((0..6).map(|_| Atom::new(1, 0.0)));
+        let mut benzene_bonds = Vec::new();
+        for i in 0..6 {
+            benzene_bonds.push(Bond::new(i, (i + 1) % 6, 1.5));
+            benzene_bonds.push(Bond::new(i, i + 6, 1.0));
+        }
+        assert_eq!(component_count(&benzene, &benzene_bonds), 1);
+        assert_eq!(component_count(&water, &[] as &[Bond]), 3);
+    }
+
+    #[test]
+    fn test_bond_kind_drives_perception() {
+        use crate::parameters::Hybridization;
+
+        // A bond type whose numeric order is always 1.0 but whose kind is explicit.
+        struct KindBond { pair: (usize, usize), kind: BondKind }
+        impl GasteigerBond for KindBond {
+            fn atom_indices(&self) -> (usize, usize) { self.pair }
+            fn bond_order(&self) -> f32 { 1.0 }
+            fn bond_kind(&self) -> BondKind { self.kind }
+        }
+
+        let mut atoms: Vec<Atom> = (0..6).map(|_| Atom::new(6, 0.0)).collect();
+        atoms.extend((0..6).map(|_| Atom::new(1, 0.0)));
+        let mut kind_bonds = Vec::new();
+        let mut numeric_bonds = Vec::new();
+        for i in 0..6 {
+            kind_bonds.push(KindBond { pair: (i, (i + 1) % 6), kind: BondKind::Aromatic });
+            kind_bonds.push(KindBond { pair: (i, i + 6), kind: BondKind::Single });
+            numeric_bonds.push(Bond::new(i, (i + 1) % 6, 1.5));
+            numeric_bonds.push(Bond::new(i, i + 6, 1.0));
+        }
+
+        let solver = GasteigerSolver::default();
+        assert_eq!(solver.perceive_hybridizations(&atoms, &kind_bonds)[0], Hybridization::Sp2);
+        assert_eq!(solver.compute_charges(&atoms, &kind_bonds), solver.compute_charges(&atoms, &numeric_bonds));
+        assert!(solver.validate(&atoms, &kind_bonds).is_ok());
+
+        assert_eq!(BondKind::from_order(1.5), BondKind::Aromatic);
+        assert_eq!(Bond::new(0, 1, 2.0).bond_kind(), BondKind::Double);
+    }
+
+    #[test]
+    fn test_diff_param_sets() {
+        use crate::parameters::{diff_param_sets, GasteigerParams, Hybridization, ParameterSet};
+
+        let builtin = ParameterSet::builtin();
+        assert!(diff_param_sets(&builtin, &builtin).is_empty());
+
+        let mut tuned = builtin.clone();
+        let hydrogen = GasteigerParams { a: 7.0, b: 6.24, c: -0.56 };
+        tuned.entries.insert((1, Hybridization::Default), hydrogen);
+        tuned.entries.remove(&(9, Hybridization::Sp3));
+
+        let diff = diff_param_sets(&builtin, &tuned);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0], (1, Hybridization::Default, builtin.entries.get(&(1, Hybridization::Default)).copied(), Some(hydrogen)));
+        assert_eq!((diff[1].0, diff[1].1, diff[1].3), (9, Hybridization::Sp3, None));
+    }
+
+    #[test]
+    fn test_neutralize_output() {
+        let ammonium = vec![Atom::new(7, 1.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+
+        let charged = GasteigerSolver::default().compute_charges(&ammonium, &bonds);
+        assert!((charged.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let solver = GasteigerSolver { neutralize_output: true, ..Default::default() };
+        let neutral = solver.compute_charges(&ammonium, &bonds);
+        assert!(neutral.iter().sum::<f64>().abs() < 1e-12);
+        assert!(((charged[0] - charged[1]) - (neutral[0] - neutral[1])).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_convergence_rate() {
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+
+        let rates = GasteigerSolver::default().convergence_rate(&methane, &bonds);
+        // The damping halves each step; the equalization itself contracts a little more.
+        assert!(rates[0] > 0.3 && rates[0] <= 0.5, "carbon rate {} should be just under the 0.5 damping", rates[0]);
+
+        let single = GasteigerSolver { iterations: 1, ..Default::default() };
+        assert!(single.convergence_rate(&methane, &bonds)[0].is_nan());
+
+        // Clamped atoms stop moving, so they report 0.0 rather than a rate.
+        let water = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let water_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let bounded = GasteigerSolver { charge_bounds: std::collections::HashMap::from([(1, (-1.0, 0.05))]), ..Default::default() };
+        assert_eq!(bounded.convergence_rate(&water, &water_bonds), vec![0.0; 3]);
+    }
+
+    #[test]
+    fn test_compute_charges_soa() {
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let solver = GasteigerSolver::default();
+
+        let charges = solver.compute_charges_soa(&[8, 1, 1], &[0.0; 3], &[0, 0], &[1, 2], &[1.0, 1.0]).unwrap();
+        assert_eq!(charges, solver.compute_charges(&atoms, &bonds));
+
+        assert_eq!(
+            solver.compute_charges_soa(&[8, 1, 1], &[0.0; 3], &[0, 0], &[1], &[1.0, 1.0]),
+            Err(SolverError::BondCountMismatch { expected: 2, found: 1 })
+        );
+    }
+
+    #[test]
+    fn test_explain_and_bond_transfers() {
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(2, 0, 1.0)];
+        let solver = GasteigerSolver::default();
+
+        let transfers = solver.bond_transfers(&atoms, &bonds);
+        assert!(transfers[0] < 0.0, "oxygen (first atom of bond 0) becomes more negative");
+        assert!(transfers[1] > 0.0, "hydrogen (first atom of bond 1) becomes more positive");
+        let charges = solver.compute_charges(&atoms, &bonds);
+        assert!((charges[0] - (transfers[0] - transfers[1])).abs() < 1e-12);
+        assert!((charges[2] - transfers[1]).abs() < 1e-12);
+
+        let text = solver.explain(&atoms, &bonds);
+        assert!(text.contains("atom 0 (Z=8, Sp3)"));
+        assert!(text.contains("bond 0: pulled charge from atom 1 (Z=1)"));
+        assert!(text.contains("bond 1: pulled charge from atom 2 (Z=1)"));
+        assert!(text.contains("bond 0: gave charge to atom 0 (Z=8)"));
+    }
+
+    #[test]
+    fn test_element_aliases_for_isotopes() {
+        // Some toolkits encode deuterium with its own code; use 1001 here.
+        const DEUTERIUM: usize = 1001;
+        let heavy_water = vec![Atom::new(8, 0.0), Atom::new(DEUTERIUM, 0.0), Atom::new(DEUTERIUM, 0.0)];
+        let water = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+
+        let plain = GasteigerSolver::default();
+        assert!(plain.compute_charges(&heavy_water, &bonds).iter().all(|&q| q == 0.0));
+
+        let solver = GasteigerSolver { element_aliases: std::collections::HashMap::from([(DEUTERIUM, 1)]), ..Default::default() };
+        assert_eq!(solver.compute_charges(&heavy_water, &bonds), plain.compute_charges(&water, &bonds));
+    }
+
+    #[test]
+    fn test_default_constants() {
+        let solver = GasteigerSolver::default();
+        assert_eq!(solver.iterations, DEFAULT_ITERATIONS);
+        assert_eq!(solver.damping, DEFAULT_DAMPING);
+    }
+
+    #[test]
+    fn test_degree_normalization() {
+        let methane = vec![Atom::new(6, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+
+        // Compare the first step, before any overshoot correction.
+        let classic = GasteigerSolver { iterations: 1, ..Default::default() };
+        let sqrt = GasteigerSolver { degree_normalization: DegreeNormalization::SqrtDegree, ..classic.clone() };
+        let degree = GasteigerSolver { degree_normalization: DegreeNormalization::Degree, ..classic.clone() };
+
+        let t_classic = classic.bond_transfers(&methane, &bonds)[0].abs();
+        let t_sqrt = sqrt.bond_transfers(&methane, &bonds)[0].abs();
+        let t_degree = degree.bond_transfers(&methane, &bonds)[0].abs();
+        assert!((t_sqrt - t_classic / 2.0).abs() < 1e-12);
+        assert!((t_degree - t_classic / 4.0).abs() < 1e-12);
+
+        let classic = GasteigerSolver::default();
+        let degree = GasteigerSolver { degree_normalization: DegreeNormalization::Degree, ..Default::default() };
+
+        let charges = degree.compute_charges(&methane, &bonds);
+        assert!(charges.iter().sum::<f64>().abs() < 1e-12);
+        assert!(charges[0].abs() < classic.compute_charges(&methane, &bonds)[0].abs());
+    }
+
+    #[test]
+    fn test_charge_outliers() {
+        // Nine carbons with similar charges, one strongly positive, and a lone oxygen.
+        let mut atoms: Vec<Atom> = (0..10).map(|_| Atom::new(6, 0.0)).collect();
+        atoms.push(Atom::new(8, 0.0));
+        let mut charges = vec![-0.05, -0.04, -0.06, -0.05, -0.05, -0.04, -0.06, -0.05, -0.05, 0.45];
+        charges.push(-0.9);
+
+        assert_eq!(analysis::charge_outliers(&atoms, &charges, 2.0).unwrap(), vec![9]);
+        assert!(analysis::charge_outliers(&atoms, &charges, 10.0).unwrap().is_empty());
+        assert!(analysis::charge_outliers(&atoms, &charges[..3], 2.0).is_err());
+    }
+
+    #[test]
+    fn test_shared_solver_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<GasteigerSolver>();
+
+        let solver = std::sync::Arc::new(GasteigerSolver::default());
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let expected = solver.compute_charges(&atoms, &bonds);
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (solver, atoms, bonds) = (solver.clone(), atoms.clone(), bonds.clone());
+                std::thread::spawn(move || solver.compute_charges(&atoms, &bonds))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_charge_bounds() {
+        let hf = vec![Atom::new(9, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0)];
+        assert!(GasteigerSolver::default().compute_charges(&hf, &bonds)[1] > 0.4);
+
+        let solver = GasteigerSolver { charge_bounds: std::collections::HashMap::from([(1, (-1.0, 0.4))]), ..Default::default() };
+        let charges = solver.compute_charges(&hf, &bonds);
+        assert!(charges[1] <= 0.4 + 1e-12, "hydrogen charge {} exceeds the cap", charges[1]);
+        assert!(charges.iter().sum::<f64>().abs() < 1e-12);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn test_read_mol2() {
+        use crate::mol2::read_mol2;
+        use crate::parameters::Hybridization;
+
+        let mut text = String::from("@<TRIPOS>MOLECULE\nbenzene\n12 12 0 0 0\nSMALL\nNO_CHARGES\n\n@<TRIPOS>ATOM\n");
+        for k in 0..6 {
+            let angle = k as f64 * std::f64::consts::PI / 3.0;
+            text += &format!("{} C{} {:.4} {:.4} 0.0000 C.ar 1 BEN 0.0000\n", k + 1, k + 1, 1.39 * angle.cos(), 1.39 * angle.sin());
+        }
+        for k in 0..6 {
+            let angle = k as f64 * std::f64::consts::PI / 3.0;
+            text += &format!("{} H{} {:.4} {:.4} 0.0000 H 1 BEN 0.0000\n", k + 7, k + 1, 2.47 * angle.cos(), 2.47 * angle.sin());
+        }
+        text += "@<TRIPOS>BOND\n";
+        for k in 0..6 {
+            text += &format!("{} {} {} ar\n", k + 1, k + 1, (k + 1) % 6 + 1);
+            text += &format!("{} {} {} 1\n", k + 7, k + 1, k + 7);
+        }
+
+        let molecules = read_mol2(text.as_bytes()).unwrap();
+        assert_eq!(molecules.len(), 1);
+        let benzene = &molecules[0];
+        assert_eq!(benzene.name, "benzene");
+        assert_eq!(benzene.atoms.len(), 12);
+        assert_eq!(benzene.bonds.len(), 12);
+        assert_eq!(benzene.hybridizations[0], Hybridization::Sp2);
+        assert_eq!(benzene.atoms[0].atom_type(), Some("C.ar"));
+        assert_eq!(benzene.atoms[6].atomic_number(), 1);
+        assert_eq!(benzene.bonds[0], Bond::new(0, 1, 1.5));
+
+        let solver = GasteigerSolver::default();
+        let charges = solver.compute_charges_with_hybridizations(&benzene.atoms, &benzene.bonds, &benzene.hybridizations).unwrap();
+        assert_eq!(charges, solver.compute_charges(&benzene.atoms, &benzene.bonds));
+        assert!(charges[0] < 0.0 && charges[6] > 0.0);
+
+        // GAFF types come from the same table as `atom_type_context`, and the
+        // parsed type reaches the solver through `atom_type`.
+        let text = "@<TRIPOS>MOLECULE\nwater\n@<TRIPOS>ATOM\n1 O 0 0 0 oh\n2 H1 1 0 0 ho\n3 H2 0 1 0 ho\n\
+                    @<TRIPOS>BOND\n1 1 2 1\n2 1 3 1\n";
+        let water = &read_mol2(text.as_bytes()).unwrap()[0];
+        assert_eq!(water.atoms[0].atomic_number, 8);
+        assert_eq!(water.hybridizations[0], Hybridization::Sp3);
+        assert_eq!(water.atoms[0].atom_type(), Some("oh"));
+        let typed = solver.compute_charges(&water.atoms, &water.bonds);
+        assert_eq!(typed, solver.compute_charges_with_hybridizations(&water.atoms, &water.bonds, &water.hybridizations).unwrap());
+        let retyped = read_mol2(text.replace(" oh\n", " O.2\n").as_bytes()).unwrap().remove(0);
+        assert_eq!(retyped.hybridizations[0], Hybridization::Sp2);
+        let plain = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        assert_eq!(solver.compute_charges(&plain, &water.bonds), typed);
+        assert_ne!(solver.compute_charges(&retyped.atoms, &retyped.bonds), typed);
+
+        assert!(read_mol2("@<TRIPOS>MOLECULE\nx\n@<TRIPOS>ATOM\n1 X 0 0 0 Zz\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_net_charge_on_site() {
+        // Methylammonium drawn without formal charges: C, N, 3 H on C, 3 H on N.
+        let mut atoms = vec![Atom::new(6, 0.0), Atom::new(7, 0.0)];
+        atoms.extend((0..6).map(|_| Atom::new(1, 0.0)));
+        let mut bonds = vec![Bond::new(0, 1, 1.0)];
+        bonds.extend((2..5).map(|h| Bond::new(0, h, 1.0)));
+        bonds.extend((5..8).map(|h| Bond::new(1, h, 1.0)));
+
+        let solver = GasteigerSolver::default();
+        let charges = solver.compute_charges_with_net_charge(&atoms, &bonds, 1, Some(1)).unwrap();
+        assert!((charges.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let mut explicit = atoms.clone();
+        explicit[1].formal_charge = 1.0;
+        assert_eq!(charges, solver.compute_charges(&explicit, &bonds));
+
+        assert_eq!(
+            solver.compute_charges_with_net_charge(&atoms, &bonds, 1, None),
+            Err(SolverError::NetChargeMismatch { expected: 1.0, found: 0.0 })
+        );
+        assert!(solver.compute_charges_with_net_charge(&explicit, &bonds, 1, None).is_ok());
+        assert!(solver.compute_charges_with_net_charge(&atoms, &bonds, 1, Some(99)).is_err());
+    }
+
+    #[test]
+    fn test_scale_for_dielectric() {
+        let atoms = vec![Atom::new(8, 0.0), Atom::new(1, 0.0), Atom::new(1, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+
+        let mut vacuum = charges.clone();
+        electrostatics::scale_for_dielectric(&mut vacuum, 1.0);
+        assert_eq!(vacuum, charges);
+
+        let mut water_screened = charges.clone();
+        electrostatics::scale_for_dielectric(&mut water_screened, 78.4);
+        for (screened, q) in water_screened.iter().zip(&charges) {
+            assert!(screened.abs() < q.abs());
+            assert!((screened * 78.4f64.sqrt() - q).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_nitro_oxygens_symmetric() {
+        // Nitromethane: C, N, O, O, 3 H; both N–O drawn as 1.5.
+        let mut atoms = vec![Atom::new(6, 0.0), Atom::new(7, 0.0), Atom::new(8, 0.0), Atom::new(8, 0.0)];
+        atoms.extend((0..3).map(|_| Atom::new(1, 0.0)));
+        let mut bonds = vec![Bond::new(0, 1, 1.0), Bond::new(1, 2, 1.5), Bond::new(3, 1, 1.5)];
+        bonds.extend((4..7).map(|h| Bond::new(0, h, 1.0)));
+
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+        assert_eq!(charges[2].to_bits(), charges[3].to_bits());
+        assert!(charges[2] < 0.0);
+        assert!((charges.iter().sum::<f64>()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_frozen_atom_keeps_seed() {
+        struct Pinned { atomic_number: usize, formal_charge: f32, frozen: bool }
+        impl GasteigerAtom for Pinned {
+            fn atomic_number(&self) -> usize { self.atomic_number }
+            fn formal_charge(&self) -> f32 { self.formal_charge }
+            fn is_frozen(&self) -> bool { self.frozen }
+        }
+        let atoms = vec![
+            Pinned { atomic_number: 8, formal_charge: -0.8, frozen: true },
+            Pinned { atomic_number: 1, formal_charge: 0.0, frozen: false },
+            Pinned { atomic_number: 1, formal_charge: 0.0, frozen: false },
+        ];
+        let bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+        assert_eq!(charges[0], -0.8f32 as f64);
+        // The pinned oxygen's χ still acts: a very negative O pushes charge onto H.
+        assert!(charges[1] < 0.0);
+        assert_eq!(charges[1], charges[2]);
+    }
+
+    #[test]
+    fn test_electric_field_radial() {
+        let charges = [1.0];
+        let coords = [[1.0, 2.0, 3.0]];
+        for (probe, direction) in [([3.0, 2.0, 3.0], [1.0, 0.0, 0.0]), ([1.0, 2.0, 1.0], [0.0, 0.0, -1.0])] {
+            let field = electrostatics::electric_field_at(&charges, &coords, probe).unwrap();
+            let expected = electrostatics::COULOMB_CONSTANT / 4.0;
+            for k in 0..3 {
+                assert!((field[k] - expected * direction[k]).abs() < 1e-12);
+            }
+        }
+        assert_eq!(electrostatics::electric_field_at(&charges, &coords, coords[0]).unwrap(), [0.0; 3]);
+        assert!(electrostatics::electric_field_at(&charges, &[], [0.0; 3]).is_err());
+    }
+
+    #[test]
+    fn test_anderson_mixing_benzene() {
+        let mut atoms: Vec<Atom> = (0..6).map(|_| Atom::new(6, 0.0)).collect();
+        atoms.extend((0..6).map(|_| Atom::new(1, 0.0)));
+        let mut bonds: Vec<Bond> = (0..6).map(|i| Bond::new(i, (i + 1) % 6, 1.5)).collect();
+        bonds.extend((0..6).map(|i| Bond::new(i, i + 6, 1.0)));
+
+        // Undamped geometric iteration has the same fixed point as Anderson
+        // mixing; with the default 0.5 damping it would stop short of it.
+        let geometric = GasteigerSolver { iterations: 200, tolerance: Some(1e-8), damping: 1.0, ..Default::default() };
+        let anderson = GasteigerSolver { damping_mode: DampingMode::Anderson { depth: 4 }, ..geometric.clone() };
+        let slow = geometric.compute_charges_with_report(&atoms, &bonds);
+        let fast = anderson.compute_charges_with_report(&atoms, &bonds);
+        assert!(slow.converged && fast.converged);
+        assert!(fast.iterations < slow.iterations, "{} vs {}", fast.iterations, slow.iterations);
+        assert!(fast.charges.iter().sum::<f64>().abs() < 1e-9);
+        for (a, b) in fast.charges.iter().zip(&slow.charges) {
+            assert!((a - b).abs() < 1e-6);
+        }
+
+        // It targets the fully equalized fixed point, like the Newton solve.
+        let newton = GasteigerSolver { mode: SolveMode::Linearized, ..Default::default() }.compute_charges(&atoms, &bonds);
+        for (a, b) in fast.charges.iter().zip(&newton) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_parameter_coverage() {
+        let solver = GasteigerSolver::default();
+        let co = vec![Atom::new(6, 0.0), Atom::new(8, 0.0)];
+        let bonds = vec![Bond::new(0, 1, 3.0)];
+        assert_eq!(solver.check_parameter_coverage(&co, &bonds), vec![(1, parameters::Hybridization::Sp)]);
+
+        let mut methane = vec![Atom::new(6, 0.0)];
+        methane.extend((0..4).map(|_| Atom::new(1, 0.0)));
+        let bonds: Vec<Bond> = (1..5).map(|h| Bond::new(0, h, 1.0)).collect();
+        assert!(solver.check_parameter_coverage(&methane, &bonds).is_empty());
+    }
+
+    #[test]
+    fn test_mixed_explicit_hybridization() {
+        use crate::parameters::Hybridization;
+        struct Typed { atomic_number: usize, hybridization: Option<Hybridization> }
+        impl GasteigerAtom for Typed {
+            fn atomic_number(&self) -> usize { self.atomic_number }
+            fn hybridization(&self) -> Option<Hybridization> { self.hybridization }
+        }
+        let ethene = |carbon: Option<Hybridization>| {
+            let mut atoms: Vec<Typed> = (0..2).map(|_| Typed { atomic_number: 6, hybridization: carbon }).collect();
+            atoms.extend((0..4).map(|_| Typed { atomic_number: 1, hybridization: None }));
+            atoms
+        };
+        let bonds = vec![
+            Bond::new(0, 1, 2.0),
+            Bond::new(0, 2, 1.0),
+            Bond::new(0, 3, 1.0),
+            Bond::new(1, 4, 1.0),
+            Bond::new(1, 5, 1.0),
+        ];
+
+        let solver = GasteigerSolver::default();
+        let guessed = solver.compute_charges(&ethene(None), &bonds);
+        assert_eq!(solver.compute_charges(&ethene(Some(Hybridization::Sp2)), &bonds), guessed);
+
+        // The explicit value wins over perception.
+        let forced = ethene(Some(Hybridization::Sp3));
+        assert_eq!(solver.perceive_hybridizations(&forced, &bonds)[..2], [Hybridization::Sp3; 2]);
+        assert_eq!(solver.perceive_hybridizations(&forced, &bonds)[2], Hybridization::Default);
+        assert_ne!(solver.compute_charges(&forced, &bonds), guessed);
+    }
+
+    #[test]
+    fn test_atoms_and_bonds_macros() {
+        let atoms = atoms![(6, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0),];
+        assert_eq!(atoms.len(), 5);
+        assert_eq!(bonds[3], Bond::new(0, 4, 1.0));
+
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+        assert!(charges[0] < 0.0);
+        assert!(charges[1..].iter().all(|&q| q == charges[1] && q > 0.0));
+    }
+
+    #[test]
+    fn test_order_hybridization_table() {
+        use crate::parameters::Hybridization;
+        // Methanimine: C=N with hydrogens.
+        let atoms = atoms![(6, 0.0), (7, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 2.0), (0, 2, 1.0), (0, 3, 1.0), (1, 4, 1.0)];
+
+        let solver = GasteigerSolver::default();
+        assert_eq!(
+            solver.order_hybridization_rules(7),
+            vec![(2.5, Hybridization::Sp), (1.25, Hybridization::Sp2)]
+        );
+        assert!(solver.order_hybridization_rules(9).is_empty());
+        assert_eq!(solver.perceive_hybridizations(&atoms, &bonds)[1], Hybridization::Sp2);
+
+        let mut custom = GasteigerSolver::default();
+        custom.order_hybridizations.insert(7, vec![(1.75, Hybridization::Sp3)]);
+        assert_eq!(custom.order_hybridization_rules(7), vec![(1.75, Hybridization::Sp3)]);
+        let hybridizations = custom.perceive_hybridizations(&atoms, &bonds);
+        assert_eq!(hybridizations[1], Hybridization::Sp3);
+        assert_eq!(hybridizations[0], Hybridization::Sp2);
+        assert_ne!(custom.compute_charges(&atoms, &bonds), solver.compute_charges(&atoms, &bonds));
+    }
+
+    #[test]
+    fn test_interaction_matrix() {
+        let solver = GasteigerSolver::default();
+        let (fluorine, bond) = (atoms![(9, 0.0), (9, 0.0)], bonds![(0, 1, 1.0)]);
+        let matrix = solver.interaction_matrix(&fluorine, &bond);
+        assert_eq!(matrix.len(), 2);
+        assert!((matrix[0][1] - matrix[1][0]).abs() < 1e-9);
+        assert!((matrix[0][0] - matrix[1][1]).abs() < 1e-9);
+        assert!(matrix[0][0] < 0.0 && matrix[0][1] > 0.0);
+
+        // Columns of a heteronuclear molecule still sum to zero.
+        let (atoms, bonds) = (atoms![(8, 0.0), (1, 0.0), (1, 0.0)], bonds![(0, 1, 1.0), (0, 2, 1.0)]);
+        let water = solver.interaction_matrix(&atoms, &bonds);
+        for k in 0..3 {
+            assert!(water.iter().map(|row| row[k]).sum::<f64>().abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_external_point_charge_field() {
+        // HCl along x; a −1 e probe 3 Å beyond the hydrogen.
+        let atoms = atoms![(1, 0.0), (17, 0.0)];
+        let bonds = bonds![(0, 1, 1.0)];
+        let coords = [[0.0, 0.0, 0.0], [1.27, 0.0, 0.0]];
+        let solver = GasteigerSolver::default();
+
+        let vacuum = solver.compute_charges(&atoms, &bonds);
+        assert_eq!(solver.compute_charges_in_field(&atoms, &bonds, &[], &coords).unwrap(), vacuum);
+
+        let polarized = solver.compute_charges_in_field(&atoms, &bonds, &[(-1.0, [-3.0, 0.0, 0.0])], &coords).unwrap();
+        assert!(polarized[0] > vacuum[0]);
+        assert!(polarized[1] < vacuum[1]);
+        assert!(polarized.iter().sum::<f64>().abs() < 1e-12);
+
+        assert!(solver.compute_charges_in_field(&atoms, &bonds, &[], &coords[..1]).is_err());
+    }
+
+    #[test]
+    fn test_remove_atoms() {
+        let methane = atoms![(6, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let methane_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0)];
+        let water = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let water_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        // Water comes first, so the methane indices must shift down.
+        let (atoms, bonds) = molecule::concat_molecules(&water, &water_bonds, &methane, &methane_bonds);
+
+        let (kept, kept_bonds, index_map) = molecule::remove_atoms(&atoms, &bonds, &[0, 1, 2]);
+        assert_eq!(kept, methane);
+        assert_eq!(kept_bonds, methane_bonds);
+        assert_eq!(index_map, vec![usize::MAX, usize::MAX, usize::MAX, 0, 1, 2, 3, 4]);
+
+        let solver = GasteigerSolver::default();
+        assert!(solver.validate(&kept, &kept_bonds).is_ok());
+        assert_eq!(solver.compute_charges(&kept, &kept_bonds), solver.compute_charges(&methane, &methane_bonds));
+    }
+
+    #[test]
+    fn test_chi_offset() {
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let solver = GasteigerSolver::default();
+        let plain = solver.compute_charges(&atoms, &bonds);
+
+        assert_eq!(solver.compute_charges_with_chi_offset(&atoms, &bonds, &[0.0; 3]).unwrap(), plain);
+        let shifted = solver.compute_charges_with_chi_offset(&atoms, &bonds, &[1.0, 0.0, 0.0]).unwrap();
+        assert!(shifted[0] < plain[0]);
+        assert!((shifted.iter().sum::<f64>()).abs() < 1e-12);
+        assert!(solver.compute_charges_with_chi_offset(&atoms, &bonds, &[1.0]).is_err());
+    }
+
+    #[test]
+    fn test_aromatic_chalcogens() {
+        use crate::parameters::Hybridization;
+        // Five-membered ring with the heteroatom at index 0, hydrogens 5..9.
+        let ring = |z: usize| {
+            let mut atoms = atoms![(z, 0.0), (6, 0.0), (6, 0.0), (6, 0.0), (6, 0.0)];
+            atoms.extend(atoms![(1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)]);
+            let mut bonds: Vec<Bond> = (0..5).map(|i| Bond::new(i, (i + 1) % 5, 1.5)).collect();
+            bonds.extend((1..5).map(|c| Bond::new(c, c + 4, 1.0)));
+            (atoms, bonds)
+        };
+        // The Gasteiger-Marsili set has no aromatic chalcogen entries, so ring O and S
+        // use the Sp2 entries, with full coverage and no fallback warnings.
+        let solver = GasteigerSolver::default();
+        for z in [16, 8] {
+            let (atoms, bonds) = ring(z);
+            assert_eq!(solver.perceive_hybridizations(&atoms, &bonds)[0], Hybridization::Sp2);
+            assert!(solver.check_parameter_coverage(&atoms, &bonds).is_empty());
+            assert!(solver.perception_warnings(&atoms, &bonds).is_empty());
+            let charges = solver.compute_charges(&atoms, &bonds);
+            assert!(charges[0] < 0.0);
+            assert!(charges.iter().sum::<f64>().abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_charges_for_element() {
+        let methane = atoms![(6, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let methane_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0)];
+        let water = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let water_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let (atoms, bonds) = molecule::concat_molecules(&methane, &methane_bonds, &water, &water_bonds);
+        let solver = GasteigerSolver::default();
+        let charges = solver.compute_charges(&atoms, &bonds);
+
+        assert_eq!(solver.charges_for_element(&atoms, &bonds, 8), vec![(5, charges[5])]);
+        assert_eq!(solver.charges_for_element(&atoms, &bonds, 1).len(), 6);
+        assert!(solver.charges_for_element(&atoms, &bonds, 7).is_empty());
+    }
+
+    #[test]
+    fn test_output_convention() {
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let plain = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+
+        let inverted = GasteigerSolver {
+            output_convention: OutputConvention { sign: -1.0, scale: 1.0 },
+            ..Default::default()
+        };
+        let negated: Vec<f64> = plain.iter().map(|q| -q).collect();
+        assert_eq!(inverted.compute_charges(&atoms, &bonds), negated);
+
+        let coulombs = GasteigerSolver {
+            output_convention: OutputConvention { sign: 1.0, scale: 1.602176634e-19 },
+            ..Default::default()
+        };
+        let scaled = coulombs.compute_charges(&atoms, &bonds);
+        assert!((scaled[0] / 1.602176634e-19 - plain[0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_overvalent_nitrogen() {
+        let solver = GasteigerSolver::default();
+        let mut atoms = atoms![(7, 0.0)];
+        atoms.extend((0..5).map(|_| Atom::new(1, 0.0)));
+        let bonds: Vec<Bond> = (1..6).map(|h| Bond::new(0, h, 1.0)).collect();
+        assert_eq!(
+            solver.validate(&atoms, &bonds),
+            Err(SolverError::Overvalence { index: 0, z: 7, total_order: 5.0 })
+        );
+
+        // Naphthalene's bridgeheads (three aromatic bonds) and aromatic
+        // 2-pyridone's carbonyl carbon count as in a Kekulé structure.
+        let mut naphthalene = vec![Atom::new(6, 0.0); 10];
+        naphthalene.extend(vec![Atom::new(1, 0.0); 8]);
+        let mut naphthalene_bonds: Vec<Bond> = [(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7), (7, 8), (8, 9), (9, 0), (4, 9)]
+            .iter()
+            .map(|&(i, j)| Bond::new(i, j, 1.5))
+            .collect();
+        naphthalene_bonds.extend([0, 1, 2, 3, 5, 6, 7, 8].iter().enumerate().map(|(k, &c)| Bond::new(c, 10 + k, 1.0)));
+        assert!(solver.validate(&naphthalene, &naphthalene_bonds).is_ok());
+        // N1 C2(=O7) C3 C4 C5 C6, with H8 on N1 and H9..H12 on C3..C6.
+        let pyridone = atoms![(7, 0.0), (6, 0.0), (6, 0.0), (6, 0.0), (6, 0.0), (6, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let pyridone_bonds = bonds![
+            (0, 1, 1.5), (1, 2, 1.5), (2, 3, 1.5), (3, 4, 1.5), (4, 5, 1.5), (5, 0, 1.5),
+            (1, 6, 2.0), (0, 7, 1.0), (2, 8, 1.0), (3, 9, 1.0), (4, 10, 1.0), (5, 11, 1.0)
+        ];
+        assert!(solver.validate(&pyridone, &pyridone_bonds).is_ok());
+        let mut overloaded = pyridone_bonds.clone();
+        overloaded[6] = Bond::new(1, 6, 3.0);
+        assert!(matches!(solver.validate(&pyridone, &overloaded), Err(SolverError::Overvalence { index: 1, .. })));
+
+        // Ammonium and pyridine-like aromatic nitrogen are fine.
+        assert!(solver.validate(&atoms[..5], &bonds[..4]).is_ok());
+        let (pyridine_n, aromatic) = (atoms![(7, 0.0), (6, 0.0), (6, 0.0)], bonds![(0, 1, 1.5), (0, 2, 1.5)]);
+        assert!(solver.validate(&pyridine_n, &aromatic).is_ok());
+    }
+
+    #[test]
+    fn test_iter_component_charges() {
+        let methane = atoms![(6, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let methane_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0)];
+        let water = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let water_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let (atoms, bonds) = molecule::concat_molecules(&methane, &methane_bonds, &water, &water_bonds);
+
+        let solver = GasteigerSolver::default();
+        let components: Vec<(Vec<usize>, Vec<f64>)> = solver.iter_component_charges(&atoms, &bonds).collect();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].0, vec![0, 1, 2, 3, 4]);
+        assert_eq!(components[0].1, solver.compute_charges(&methane, &methane_bonds));
+        assert_eq!(components[1].0, vec![5, 6, 7]);
+        assert_eq!(components[1].1, solver.compute_charges(&water, &water_bonds));
+    }
+
+    #[test]
+    fn test_charge_flow() {
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0), (9, 0.0), (9, 0.0)];
+        // The second O–H bond lists hydrogen first.
+        let bonds = bonds![(0, 1, 1.0), (2, 0, 1.0), (3, 4, 1.0)];
+        let flow = GasteigerSolver::default().charge_flow(&atoms, &bonds);
+        assert_eq!(flow, vec![Some((1, 0)), Some((2, 0)), None]);
+
+        let newton = GasteigerSolver { mode: SolveMode::Linearized, ..Default::default() };
+        assert!(newton.charge_flow(&atoms, &bonds).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_typed_bond_order() {
+        use crate::parameters::Hybridization;
+        // `bond_order` is deliberately wrong; the enum must win.
+        struct TypedBond { pair: (usize, usize), order: BondOrder }
+        impl GasteigerBond for TypedBond {
+            fn atom_indices(&self) -> (usize, usize) { self.pair }
+            fn bond_order(&self) -> f32 { 1.0 }
+            fn typed_order(&self) -> Option<BondOrder> { Some(self.order) }
+        }
+        let mut atoms: Vec<Atom> = (0..6).map(|_| Atom::new(6, 0.0)).collect();
+        atoms.extend((0..6).map(|_| Atom::new(1, 0.0)));
+        let mut typed = Vec::new();
+        let mut plain = Vec::new();
+        for i in 0..6 {
+            typed.push(TypedBond { pair: (i, (i + 1) % 6), order: BondOrder::Aromatic });
+            typed.push(TypedBond { pair: (i, i + 6), order: BondOrder::Single });
+            plain.push(Bond::new(i, (i + 1) % 6, 1.5));
+            plain.push(Bond::new(i, i + 6, 1.0));
+        }
+
+        let solver = GasteigerSolver::default();
+        assert!(solver.perceive_hybridizations(&atoms, &typed)[..6].iter().all(|&h| h == Hybridization::Sp2));
+        assert_eq!(solver.compute_charges(&atoms, &typed), solver.compute_charges(&atoms, &plain));
+        assert!(solver.validate(&atoms, &typed).is_ok());
+
+        assert_eq!(BondOrder::from_f32(1.5), BondOrder::Aromatic);
+        assert_eq!(BondOrder::from_f32(1.33), BondOrder::Partial(1.33));
+        assert_eq!(BondOrder::Partial(1.33).as_f32(), 1.33);
+        assert!(BondOrder::Partial(1.4).is_aromatic() && !BondOrder::Double.is_aromatic());
+        assert_eq!(BondOrder::from(BondKind::Triple).as_f32(), 3.0);
+    }
+
+    #[test]
+    fn test_calibrate_reduces_rmsd() {
+        use crate::parameters::ParameterSet;
+        let water = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let water_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let methanol = atoms![(6, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let methanol_bonds = bonds![(0, 1, 1.0), (1, 5, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0)];
+
+        let solver = GasteigerSolver::default();
+        // Targets: the current charges with oxygen pushed 0.05 e more negative.
+        let perturb = |atoms: &[Atom], charges: Vec<f64>| -> Vec<f64> {
+            let n = charges.len() as f64;
+            charges
+                .iter()
+                .zip(atoms)
+                .map(|(q, a)| if a.atomic_number == 8 { q - 0.05 + 0.05 / n } else { q + 0.05 / n })
+                .collect()
+        };
+        let water_ref = perturb(&water, solver.compute_charges(&water, &water_bonds));
+        let methanol_ref = perturb(&methanol, solver.compute_charges(&methanol, &methanol_bonds));
+        let samples = [
+            (&water[..], &water_bonds[..], &water_ref[..]),
+            (&methanol[..], &methanol_bonds[..], &methanol_ref[..]),
+        ];
+
+        let rmsd = |solver: &GasteigerSolver| {
+            let mut all = solver.compute_charges(&water, &water_bonds);
+            all.extend(solver.compute_charges(&methanol, &methanol_bonds));
+            let mut reference = water_ref.clone();
+            reference.extend(&methanol_ref);
+            analysis::rmsd_to_reference(&all, &reference).unwrap()
+        };
+
+        let fitted = solver.calibrate(&samples, &[8], 20).unwrap();
+        assert_eq!(fitted.entries.len(), 1);
+        let mut table = ParameterSet::builtin();
+        table.entries.extend(fitted.entries);
+        let refit = GasteigerSolver { param_provider: std::sync::Arc::new(table), ..Default::default() };
+        assert!(rmsd(&refit) < 0.5 * rmsd(&solver));
+
+        let short = [(&water[..], &water_bonds[..], &water_ref[..2])];
+        assert!(solver.calibrate(&short, &[8], 1).is_err());
+    }
+
+    #[test]
+    fn test_charge_cache() {
+        let solver = GasteigerSolver::default();
+        let mut cache = ChargeCache::new(&solver);
+        let mut atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+
+        let first = cache.solve(&atoms, &bonds);
+        let second = cache.solve(&atoms, &bonds);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert_eq!(first, second);
+        assert_eq!(first, solver.compute_charges(&atoms, &bonds));
+
+        // A formal-charge change is a different molecule.
+        atoms[0].formal_charge = -1.0;
+        let anion = cache.solve(&atoms, &bonds);
+        assert_eq!((cache.hits(), cache.misses(), cache.len()), (1, 2, 2));
+        assert_ne!(anion, first);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_seeded_parallel_solve() {
+        // Five disconnected water molecules plus a methane.
+        let water = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let water_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let mut atoms = atoms![(6, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let mut bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0)];
+        for _ in 0..5 {
+            (atoms, bonds) = molecule::concat_molecules(&atoms, &bonds, &water, &water_bonds);
+        }
+
+        let seeded = GasteigerSolver { seed: Some(42), ..Default::default() };
+        let first = seeded.compute_charges_parallel(&atoms, &bonds, 3);
+        let second = seeded.compute_charges_parallel(&atoms, &bonds, 3);
+        let bits = |q: &[f64]| q.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&first), bits(&second));
+
+        let split = GasteigerSolver { split_components: true, ..Default::default() };
+        assert_eq!(bits(&first), bits(&split.compute_charges(&atoms, &bonds)));
+        let other = GasteigerSolver { seed: Some(7), ..Default::default() };
+        assert_eq!(bits(&first), bits(&other.compute_charges_parallel(&atoms, &bonds, 2)));
+    }
+
+    #[test]
+    fn test_surface_weighted_charges() {
+        // Methanol: the hydroxyl hydrogen is terminal, the carbon has four neighbors.
+        let atoms = atoms![(6, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (1, 5, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0)];
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+        // Hydrogen 4 has a NaN radius and hydrogen 5 none; both use covalent radii.
+        let radii = [1.7, 1.52, 1.2, 1.2, f64::NAN];
+
+        let weighted = analysis::surface_weighted_charges(&atoms, &bonds, &charges, &radii).unwrap();
+        assert!(weighted[5] > weighted[0]);
+        assert!((weighted[0] - charges[0] * 4.0 * std::f64::consts::PI * 1.7 * 1.7 / 5.0).abs() < 1e-12);
+        assert!(weighted[4] > 0.0 && weighted[4] < weighted[3]);
+        assert!(weighted[5] > 0.0);
+
+        assert!(analysis::surface_weighted_charges(&atoms, &bonds, &charges[..2], &radii).is_err());
+    }
+
+    #[test]
+    fn test_boltzmann_average_charges() {
+        // Methylamine and methylammonium; the extra proton is an unbonded dummy in the neutral state.
+        let neutral = atoms![(6, 0.0), (7, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (0, 0.0)];
+        let mut cation = neutral.clone();
+        cation[1].formal_charge = 1.0;
+        cation[7].atomic_number = 1;
+        let mut bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0), (1, 5, 1.0), (1, 6, 1.0)];
+        let neutral_bonds = bonds.clone();
+        bonds.push(Bond::new(1, 7, 1.0));
+
+        let solver = GasteigerSolver::default();
+        let a = solver.compute_charges(&neutral, &neutral_bonds);
+        let b = solver.compute_charges(&cation, &bonds);
+        let states = [(&neutral[..], &neutral_bonds[..], 0.0), (&cation[..], &bonds[..], 0.0)];
+        let average = solver.boltzmann_average_charges(&states, 298.15).unwrap();
+        for ((q, x), y) in average.iter().zip(&a).zip(&b) {
+            assert!((q - (x + y) / 2.0).abs() < 1e-12);
+        }
+        assert!((average.iter().sum::<f64>() - 0.5).abs() < 1e-12);
+
+        // A state 10 kcal/mol higher barely contributes.
+        let biased = [(&neutral[..], &neutral_bonds[..], 0.0), (&cation[..], &bonds[..], 10.0)];
+        let mostly_neutral = solver.boltzmann_average_charges(&biased, 298.15).unwrap();
+        assert!(mostly_neutral.iter().sum::<f64>() < 1e-6);
+
+        let short = [(&neutral[..], &neutral_bonds[..], 0.0), (&cation[..7], &neutral_bonds[..], 0.0)];
+        assert!(solver.boltzmann_average_charges(&short, 298.15).is_err());
+    }
+
+    #[test]
+    fn test_infers_ammonium_and_carboxylate_formal_charges() {
+        // NH4+ next to acetate: C(H3)-C(=O)-O-
+        let atoms = atoms![(7, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (6, 0.0), (6, 0.0), (8, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0), (5, 6, 1.0), (6, 7, 2.0), (6, 8, 1.0), (5, 9, 1.0), (5, 10, 1.0), (5, 11, 1.0)];
+        let inferred = molecule::infer_formal_charges(&atoms, &bonds);
+        assert_eq!(inferred[0], 1.0);
+        assert_eq!(inferred[7], 0.0);
+        assert_eq!(inferred[8], -1.0);
+        assert!(inferred.iter().enumerate().all(|(k, &q)| q == 0.0 || k == 0 || k == 8));
+    }
+
+    #[test]
+    fn test_l2_convergence_norm_stops_no_later_than_l_infinity() {
+        // Ethanol: most atoms settle quickly, the C-O-H end lags behind.
+        let atoms = atoms![(6, 0.0), (6, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (1, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0), (0, 5, 1.0), (1, 6, 1.0), (1, 7, 1.0), (2, 8, 1.0)];
+        let max_norm = GasteigerSolver { tolerance: Some(1e-3), iterations: 100, ..Default::default() };
+        let rms_norm = GasteigerSolver { convergence_norm: ConvergenceNorm::L2, ..max_norm.clone() };
+
+        let max_result = max_norm.compute_charges_with_report(&atoms, &bonds);
+        let rms_result = rms_norm.compute_charges_with_report(&atoms, &bonds);
+        assert!(max_result.converged && rms_result.converged);
+        assert!(rms_result.iterations < max_result.iterations);
+        assert_eq!(ConvergenceNorm::L2.measure(&[3.0, 4.0]), (12.5f64).sqrt());
+        assert_eq!(ConvergenceNorm::LInfinity.measure(&[3.0, -4.0]), 4.0);
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn test_writes_water_pqr_with_default_radii() {
+        struct Placed(Atom, [f64; 3]);
+        impl GasteigerAtom for Placed {
+            fn atomic_number(&self) -> usize { self.0.atomic_number }
+        }
+        impl PositionedAtom for Placed {
+            fn position(&self) -> [f64; 3] { self.1 }
+        }
+
+        let atoms = vec![
+            Placed(Atom::new(8, 0.0), [0.0, 0.0, 0.0]),
+            Placed(Atom::new(1, 0.0), [0.9572, 0.0, 0.0]),
+            Placed(Atom::new(1, 0.0), [-0.2400, 0.9266, 0.0]),
+        ];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let charges = GasteigerSolver::default().compute_charges(&atoms, &bonds);
+
+        let mut out = Vec::new();
+        pqr::write_pqr(&mut out, &atoms, &charges, &[1.6]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let records: Vec<Vec<&str>> = text.lines().filter(|l| l.starts_with("ATOM")).map(|l| l.split_whitespace().collect()).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1][2], "H1");
+        for (record, &q) in records.iter().zip(&charges) {
+            assert!((record[8].parse::<f64>().unwrap() - q).abs() < 1e-4);
+        }
+        assert_eq!(records[0][9], "1.6000");
+        assert_eq!(records[2][9], "1.2000");
+        assert!(text.ends_with("TER\nEND\n"));
+        assert!(pqr::write_pqr(Vec::new(), &atoms, &charges[..2], &[]).is_err());
+    }
+
+    #[test]
+    fn test_charge_difference_between_bond_arrangements() {
+        // Acrolein C1=C2-C3=O4 (s-cis and s-trans share this topology) versus
+        // the rearranged allenol-like C1=C2=C3-O4, both with H5..H8 in place.
+        let atoms = atoms![(6, 0.0), (6, 0.0), (6, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let acrolein = bonds![(0, 1, 2.0), (1, 2, 1.0), (2, 3, 2.0), (0, 4, 1.0), (0, 5, 1.0), (1, 6, 1.0), (2, 7, 1.0)];
+        let rearranged = bonds![(0, 1, 2.0), (1, 2, 2.0), (2, 3, 1.0), (0, 4, 1.0), (0, 5, 1.0), (1, 6, 1.0), (3, 7, 1.0)];
+        let solver = GasteigerSolver::default();
+
+        let conformers = solver.charge_difference((&atoms, &acrolein), (&atoms, &acrolein)).unwrap();
+        assert!(conformers.iter().all(|&d| d == 0.0));
+
+        let diff = solver.charge_difference((&atoms, &acrolein), (&atoms, &rearranged)).unwrap();
+        assert_eq!(diff.len(), atoms.len());
+        assert!(diff.iter().sum::<f64>().abs() < 1e-12);
+        let a = solver.compute_charges(&atoms, &acrolein);
+        let b = solver.compute_charges(&atoms, &rearranged);
+        assert!(diff.iter().zip(a.iter().zip(&b)).all(|(d, (qa, qb))| (d - (qb - qa)).abs() < 1e-15));
+        assert!(diff[7] > 0.05, "H on oxygen should gain positive charge");
+
+        let shorter = atoms![(6, 0.0)];
+        let none = bonds![];
+        assert!(solver.charge_difference((&atoms, &acrolein), (&shorter, &none)).is_err());
+    }
+
+    #[test]
+    fn test_atom_type_selects_parameters() {
+        use crate::parameters::{get_params, get_params_by_type, GasteigerParams, Hybridization};
+
+        struct TypedAtom(usize, &'static str);
+        impl GasteigerAtom for TypedAtom {
+            fn atomic_number(&self) -> usize { self.0 }
+            fn atom_type(&self) -> Option<&str> { Some(self.1) }
+        }
+
+        assert_eq!(get_params_by_type("C.3"), get_params(6, Hybridization::Sp3));
+        assert_eq!(get_params_by_type("ca"), get_params(6, Hybridization::Sp2));
+        assert_eq!(get_params_by_type("X.9"), None);
+
+        struct FirstAtom(Option<(Hybridization, Option<GasteigerParams>)>);
+        impl ResolutionObserver for FirstAtom {
+            fn on_atom_resolved(&mut self, index: usize, _: usize, guessed: Hybridization, resolved: Option<&GasteigerParams>) {
+                if index == 0 {
+                    self.0 = Some((guessed, resolved.copied()));
+                }
+            }
+        }
+
+        // An sp2-bonded carbon typed as C.3 is solved with Sp3 parameters.
+        let typed = vec![TypedAtom(6, "C.3"), TypedAtom(8, "O.2")];
+        let bonds = bonds![(0, 1, 2.0)];
+        let solver = GasteigerSolver::default();
+        let mut first = FirstAtom(None);
+        solver.compute_charges_observed(&typed, &bonds, &mut first);
+        assert_eq!(first.0, Some((Hybridization::Sp3, get_params_by_type("C.3"))));
+
+        let plain = atoms![(6, 0.0), (8, 0.0)];
+        let explicit = solver.compute_charges_with_hybridizations(&plain, &bonds, &[Hybridization::Sp3, Hybridization::Sp2]).unwrap();
+        assert_eq!(solver.compute_charges(&typed, &bonds), explicit);
+
+        // A type naming another element is ignored.
+        let mismatched = vec![TypedAtom(6, "N.3"), TypedAtom(8, "O.2")];
+        assert_eq!(solver.compute_charges(&mismatched, &bonds), solver.compute_charges(&plain, &bonds));
+    }
+
+    #[test]
+    fn test_batch_iteration_budget_flags_later_molecules() {
+        let water = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let water_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let methanol = atoms![(6, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let methanol_bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0), (1, 5, 1.0)];
+        let solver = GasteigerSolver { tolerance: Some(1e-6), iterations: 100, ..Default::default() };
+        let needed = solver.compute_charges_with_report(&water, &water_bonds).iterations;
+
+        let batch: [(&[Atom], &[Bond]); 3] = [(&water, &water_bonds), (&methanol, &methanol_bonds), (&water, &water_bonds)];
+        let results = solver.compute_charges_batch(&batch, needed + 2);
+        assert!(results[0].converged);
+        assert_eq!(results[0].iterations, needed);
+        assert!(!results[1].converged);
+        assert_eq!(results[1].iterations, 2);
+        assert!(!results[2].converged);
+        assert_eq!(results[2].iterations, 0);
+        assert!(results[2].charges.iter().all(|&q| q == 0.0));
+
+        let unlimited = solver.compute_charges_batch(&batch, usize::MAX);
+        assert!(unlimited.iter().all(|r| r.converged));
+        assert_eq!(unlimited[1], solver.compute_charges_with_report(&methanol, &methanol_bonds));
+    }
+
+    #[test]
+    fn test_naphthalene_charges_follow_ring_symmetry() {
+        // C1..C4 = 0..3, C4a = 4, C5..C8 = 5..8, C8a = 9, then H on C1..C4 and C5..C8.
+        let mut atoms = vec![Atom::new(6, 0.0); 10];
+        atoms.extend(vec![Atom::new(1, 0.0); 8]);
+        let ring = [(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6), (6, 7), (7, 8), (8, 9), (9, 0), (4, 9)];
+        let carbons_with_h = [0, 1, 2, 3, 5, 6, 7, 8];
+        let hydrogens = carbons_with_h.iter().enumerate().map(|(k, &c)| Bond::new(c, 10 + k, 1.0));
+        let aromatic: Vec<Bond> = ring.iter().map(|&(i, j)| Bond::new(i, j, 1.5)).chain(hydrogens.clone()).collect();
+        let kekule_orders = [2.0, 1.0, 2.0, 1.0, 1.0, 2.0, 1.0, 2.0, 1.0, 1.0, 2.0];
+        let kekule: Vec<Bond> = ring.iter().zip(kekule_orders).map(|(&(i, j), o)| Bond::new(i, j, o)).chain(hydrogens).collect();
+
+        let solver = GasteigerSolver::default();
+        assert!(solver.perception_warnings(&atoms, &aromatic).is_empty());
+        let charges = solver.compute_charges_checked(&atoms, &aromatic).unwrap();
+        assert!(charges.iter().sum::<f64>().abs() < 1e-12);
+
+        let alpha = [0, 3, 5, 8];
+        let beta = [1, 2, 6, 7];
+        for class in [&alpha[..], &beta[..], &[4, 9][..]] {
+            assert!(class.iter().all(|&c| (charges[c] - charges[class[0]]).abs() < 1e-12), "{:?}", class);
+        }
+        assert!((charges[alpha[0]] - charges[beta[0]]).abs() > 1e-6);
+        assert!((charges[alpha[0]] - charges[4]).abs() > 1e-6);
+
+        // The bridgeheads are Sp2 in the Kekulé form too, so the charges agree.
+        let kekule_charges = solver.compute_charges_checked(&atoms, &kekule).unwrap();
+        assert!(charges.iter().zip(&kekule_charges).all(|(a, b)| (a - b).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_resolved_params_for_water_oxygen() {
+        use crate::parameters::{get_params, Hybridization};
+
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0), (46, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let solver = GasteigerSolver::default();
+        assert_eq!(solver.resolved_params_for(0, &atoms, &bonds), Some((Hybridization::Sp3, get_params(8, Hybridization::Sp3).unwrap())));
+        assert_eq!(solver.resolved_params_for(1, &atoms, &bonds), Some((Hybridization::Default, get_params(1, Hybridization::Default).unwrap())));
+        assert_eq!(solver.resolved_params_for(3, &atoms, &bonds), None);
+        assert_eq!(solver.resolved_params_for(4, &atoms, &bonds), None);
+
+        // Polar hydrogen parameters replace the table entry but keep the perceived state.
+        let polar = GasteigerSolver { polar_hydrogen_params: parameters::polar_hydrogen_params(), ..Default::default() };
+        assert_eq!(polar.resolved_params_for(1, &atoms, &bonds), Some((Hybridization::Default, parameters::polar_hydrogen_params()[&8])));
+    }
+
+    #[test]
+    fn test_symmetrized_charges_equalize_methane_hydrogens() {
+        let atoms = atoms![(6, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0)];
+        let solver = GasteigerSolver::default();
+        let (raw, symmetrized) = solver.compute_charges_symmetrized(&atoms, &bonds);
+        assert_eq!(raw, solver.compute_charges(&atoms, &bonds));
+        assert!(raw[1..].iter().all(|&q| (q - raw[1]).abs() < 1e-12));
+        assert!(symmetrized[1..].iter().all(|&q| q == symmetrized[1]));
+        assert!((symmetrized.iter().sum::<f64>() - raw.iter().sum::<f64>()).abs() < 1e-12);
+        assert_eq!(molecule::equivalence_classes(&atoms, &bonds), vec![1, 0, 0, 0, 0]);
+
+        // Ethanol's CH2 and CH3 hydrogens stay in separate classes.
+        let ethanol = atoms![(6, 0.0), (6, 0.0), (8, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let ethanol_bonds = bonds![(0, 1, 1.0), (1, 2, 1.0), (0, 3, 1.0), (0, 4, 1.0), (0, 5, 1.0), (1, 6, 1.0), (1, 7, 1.0), (2, 8, 1.0)];
+        let (raw, symmetrized) = solver.compute_charges_symmetrized(&ethanol, &ethanol_bonds);
+        assert_eq!(symmetrized[3], symmetrized[5]);
+        assert_eq!(symmetrized[6], symmetrized[7]);
+        assert!((symmetrized[3] - symmetrized[6]).abs() > 1e-6);
+        assert!((symmetrized[8] - raw[8]).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_target_total_with_output_options() {
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let plain = GasteigerSolver::default().compute_charges_with_total(&atoms, &bonds, 1.0);
+
+        // Rounding applies to the shifted charges.
+        let rounded = GasteigerSolver { output_decimals: Some(2), ..Default::default() };
+        let charges = rounded.compute_charges_with_total(&atoms, &bonds, 1.0);
+        assert!(charges.iter().zip(&plain).all(|(q, p)| *q == (p * 100.0).round() / 100.0));
+
+        // The target is in elementary charges, converted like every other charge.
+        let inverted = GasteigerSolver { output_convention: OutputConvention { sign: -1.0, scale: 2.0 }, ..Default::default() };
+        let charges = inverted.compute_charges_with_total(&atoms, &bonds, 1.0);
+        assert!((charges.iter().sum::<f64>() + 2.0).abs() < 1e-12);
+        assert!(charges.iter().zip(&plain).all(|(q, p)| (q + 2.0 * p).abs() < 1e-12));
+
+        // An explicit target replaces neutralization.
+        let neutralizing = GasteigerSolver { neutralize_output: true, ..Default::default() };
+        assert_eq!(neutralizing.compute_charges_with_total(&atoms, &bonds, 1.0), plain);
+        assert!(neutralizing.compute_charges(&atoms, &bonds).iter().sum::<f64>().abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_explain_uses_aliased_element() {
+        let mut solver = GasteigerSolver::default();
+        solver.element_aliases.insert(1001, 1);
+        let atoms = atoms![(8, 0.0), (1001, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let report = solver.explain(&atoms, &bonds);
+        let hydrogen = parameters::get_params(1, parameters::Hybridization::Default).unwrap();
+        let deuterium_line = report.lines().find(|l| l.starts_with("atom 1 ")).unwrap();
+        assert!(deuterium_line.starts_with("atom 1 (Z=1 via alias 1001,"), "{}", deuterium_line);
+        assert!(deuterium_line.contains(&format!("params a={} b={} c={}", hydrogen.a, hydrogen.b, hydrogen.c)));
+        assert!(report.contains("atom 1 (Z=1),"));
+    }
+
+    #[test]
+    fn test_terminal_oxygen_symmetry_respects_per_atom_inputs() {
+        use crate::parameters::Hybridization;
+
+        struct Pinned { atomic_number: usize, formal_charge: f32, frozen: bool }
+        impl GasteigerAtom for Pinned {
+            fn atomic_number(&self) -> usize { self.atomic_number }
+            fn formal_charge(&self) -> f32 { self.formal_charge }
+            fn is_frozen(&self) -> bool { self.frozen }
+        }
+
+        // Acetate with delocalized C-O bonds: C0(H4,H5,H6)-C1(O2)(O3).
+        let atoms = atoms![(6, 0.0), (6, 0.0), (8, -0.5), (8, -0.5), (1, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (1, 2, 1.5), (1, 3, 1.5), (0, 4, 1.0), (0, 5, 1.0), (0, 6, 1.0)];
+        let solver = GasteigerSolver::default();
+        let plain = solver.compute_charges(&atoms, &bonds);
+        assert_eq!(plain[2], plain[3]);
+
+        let offset = solver.compute_charges_with_chi_offset(&atoms, &bonds, &[0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0]).unwrap();
+        assert!(offset[2] < offset[3] - 0.01);
+
+        let coords = [[0.0, 0.0, 0.0], [1.5, 0.0, 0.0], [2.2, 1.1, 0.0], [2.2, -1.1, 0.0], [-0.4, 1.0, 0.0], [-0.4, -0.5, 0.9], [-0.4, -0.5, -0.9]];
+        let field = solver.compute_charges_in_field(&atoms, &bonds, &[(1.0, [2.2, 3.1, 0.0])], &coords).unwrap();
+        assert!((field[2] - field[3]).abs() > 1e-4);
+
+        let hybridizations = [Hybridization::Sp3, Hybridization::Sp2, Hybridization::Sp3, Hybridization::Sp2, Hybridization::Default, Hybridization::Default, Hybridization::Default];
+        let typed = solver.compute_charges_with_hybridizations(&atoms, &bonds, &hybridizations).unwrap();
+        assert!((typed[2] - typed[3]).abs() > 1e-4);
+
+        let pinned: Vec<Pinned> = atoms
+            .iter()
+            .enumerate()
+            .map(|(i, a)| Pinned { atomic_number: a.atomic_number, formal_charge: a.formal_charge, frozen: i == 2 })
+            .collect();
+        let frozen = solver.compute_charges(&pinned, &bonds);
+        assert_eq!(frozen[2], -0.5);
+        assert_ne!(frozen[3], -0.5);
+    }
+
+    #[test]
+    fn test_frozen_atoms_in_bounds_and_every_mode() {
+        struct Pinned { atomic_number: usize, formal_charge: f32, frozen: bool }
+        impl GasteigerAtom for Pinned {
+            fn atomic_number(&self) -> usize { self.atomic_number }
+            fn formal_charge(&self) -> f32 { self.formal_charge }
+            fn is_frozen(&self) -> bool { self.frozen }
+        }
+
+        // H frozen at +0.3 next to a bounded F: the clipped charge may not land on H.
+        let hf = vec![
+            Pinned { atomic_number: 1, formal_charge: 0.3, frozen: true },
+            Pinned { atomic_number: 9, formal_charge: 0.0, frozen: false },
+        ];
+        let hf_bonds = vec![Bond::new(0, 1, 1.0)];
+        let mut bounded = GasteigerSolver::default();
+        bounded.charge_bounds.insert(9, (-0.2, 0.2));
+        let charges = bounded.compute_charges(&hf, &hf_bonds);
+        assert_eq!(charges[0], 0.3f32 as f64);
+        assert_eq!(
+            bounded.compute_charges_checked(&hf, &hf_bonds),
+            Err(SolverError::BoundedAtomWithoutAbsorber { index: 1 })
+        );
+
+        // A frozen oxygen keeps its seed in the Anderson and linearized modes too.
+        let water = vec![
+            Pinned { atomic_number: 8, formal_charge: -0.8, frozen: true },
+            Pinned { atomic_number: 1, formal_charge: 0.0, frozen: false },
+            Pinned { atomic_number: 1, formal_charge: 0.0, frozen: false },
+        ];
+        let water_bonds = vec![Bond::new(0, 1, 1.0), Bond::new(0, 2, 1.0)];
+        let modes = [
+            GasteigerSolver { mode: SolveMode::Linearized, ..Default::default() },
+            GasteigerSolver { damping_mode: DampingMode::Anderson { depth: 3 }, iterations: 50, tolerance: Some(1e-10), ..Default::default() },
+        ];
+        for solver in &modes {
+            let charges = solver.compute_charges(&water, &water_bonds);
+            assert_eq!(charges[0], -0.8f32 as f64);
+            assert!(charges[1] < 0.0);
+            assert_eq!(charges[1], charges[2]);
+        }
+        #[cfg(feature = "simd")]
+        assert_eq!(GasteigerSolver::default().compute_charges_simd(&water, &water_bonds)[0], -0.8);
+    }
+
+    #[test]
+    fn test_boltzmann_average_rejects_bad_temperature() {
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let states: [(&[Atom], &[Bond], f64); 1] = [(&atoms, &bonds, 0.0)];
+        let solver = GasteigerSolver::default();
+        for temperature in [0.0, -10.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                solver.boltzmann_average_charges(&states, temperature),
+                Err(SolverError::InvalidTemperature { .. })
+            ));
+        }
+        assert_eq!(solver.boltzmann_average_charges(&states, 298.15).unwrap(), solver.compute_charges(&atoms, &bonds));
+    }
+
+    #[test]
+    fn test_anderson_respects_charge_bounds() {
+        use crate::solver::DampingMode;
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let bounds = std::collections::HashMap::from([(1, (-1.0, 0.1))]);
+        let solver = GasteigerSolver {
+            damping_mode: DampingMode::Anderson { depth: 3 },
+            damping: 1.0,
+            iterations: 50,
+            tolerance: Some(1e-9),
+            charge_bounds: bounds,
+            ..Default::default()
+        };
+        let result = solver.compute_charges_with_report(&atoms, &bonds);
+        assert!(result.converged);
+        for &h in &result.charges[1..] {
+            assert!((h - 0.1).abs() < 1e-12, "hydrogen charge {} ignores the bound", h);
+        }
+        assert!(result.charges.iter().sum::<f64>().abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_linearized_rejects_charge_bounds() {
+        let atoms = atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+        let bonds = bonds![(0, 1, 1.0), (0, 2, 1.0)];
+        let bounded = GasteigerSolver { charge_bounds: std::collections::HashMap::from([(1, (-1.0, 0.1))]), ..Default::default() };
+        assert!(bounded.validate(&atoms, &bonds).is_ok());
+        let linearized = GasteigerSolver { mode: SolveMode::Linearized, ..bounded };
+        assert_eq!(linearized.validate(&atoms, &bonds), Err(SolverError::UnsupportedChargeBounds { index: 1 }));
+        let unbounded = atoms![(8, 0.0), (9, 0.0)];
+        let single = bonds![(0, 1, 1.0)];
+        assert!(linearized.validate(&unbounded, &single).is_ok());
+    }
 }
@@ -0,0 +1,177 @@
+//! Newton solve of the fully equalized PEOE fixed point.
+
+use crate::model::ElectronegativityModel;
+use crate::solver::{GasteigerSolver, Topology};
+use crate::topology::component_labels;
+use crate::traits::{GasteigerAtom, GasteigerBond};
+
+/// Upper bound on Newton steps in `SolveMode::Linearized`.
+pub const MAX_NEWTON_STEPS: usize = 50;
+
+/// Residual tolerance used by `SolveMode::Linearized` when the solver has none,
+/// compared against the residual under the solver's `convergence_norm`.
+pub const LINEARIZED_TOLERANCE: f64 = 1e-10;
+
+/// Finite-difference step for the Jacobian columns.
+const JACOBIAN_STEP: f64 = 1e-6;
+
+impl<M: ElectronegativityModel> GasteigerSolver<M> {
+    /// Interaction matrix `J[i][k] = ∂rᵢ/∂qₖ` of the undamped per-atom transfer
+    /// `r(q)`, evaluated at the charges the configured schedule produces (before
+    /// rounding, neutralization or virtual-site relocation).
+    ///
+    /// Each bond `(i, j)` contributes `(χⱼ(qⱼ) - χᵢ(qᵢ)) / χ⁺` to `rᵢ` and the
+    /// opposite to `rⱼ`, where `χ⁺` belongs to the less electronegative atom (the
+    /// first atom at a tie). Iteration `k` of the damped schedule moves charges by
+    /// `damping^k · r(q)`, so near the result `J` is the linear map the iteration
+    /// contracts with, and it is the matrix `SolveMode::Linearized` solves against
+    /// at every Newton step. Rows and columns of atoms without parameters are zero,
+    /// and every column sums to zero because transfers conserve charge. The matrix
+    /// is symmetric only when bonded atoms share parameters and `χ⁺`. `dχ/dq` is
+    /// taken by central differences, so any model works.
+    pub fn interaction_matrix<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<Vec<f64>>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let topology = self.prepare(atoms, bonds);
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        self.equalize_molecule(&mut charges, &topology);
+
+        let n = atoms.len();
+        let mut matrix = vec![vec![0.0; n]; n];
+        for &(i, j) in &topology.pairs {
+            let (Some(p_i), Some(p_j)) = (&topology.params[i], &topology.params[j]) else { continue };
+            let chi = |p, q: f64| self.model.electronegativity(p, q);
+            let slope = |p, q: f64| (chi(p, q + JACOBIAN_STEP) - chi(p, q - JACOBIAN_STEP)) / (2.0 * JACOBIAN_STEP);
+            let (chi_i, chi_j) = (chi(p_i, charges[i]) + topology.chi_offsets[i], chi(p_j, charges[j]) + topology.chi_offsets[j]);
+            let denom = if chi_j >= chi_i { topology.chi_plus[i] } else { topology.chi_plus[j] };
+            let (d_i, d_j) = (slope(p_i, charges[i]) / denom, slope(p_j, charges[j]) / denom);
+            matrix[i][i] -= d_i;
+            matrix[i][j] += d_j;
+            matrix[j][i] += d_i;
+            matrix[j][j] -= d_j;
+        }
+        matrix
+    }
+
+    /// Undamped sum of per-bond transfers for each atom; zero at the fixed point.
+    /// Frozen atoms never move, so their entries are always zero.
+    pub(crate) fn transfer_residual(&self, charges: &[f64], topology: &Topology) -> Vec<f64> {
+        let mut residual = vec![0.0; charges.len()];
+        for &(i, j) in &topology.pairs {
+            if let (Some(p_i), Some(p_j)) = (&topology.params[i], &topology.params[j]) {
+                let chi_i = self.model.electronegativity(p_i, charges[i]) + topology.chi_offsets[i];
+                let chi_j = self.model.electronegativity(p_j, charges[j]) + topology.chi_offsets[j];
+                if chi_j > chi_i {
+                    let dq = (chi_j - chi_i) / topology.chi_plus[i];
+                    residual[i] += dq;
+                    residual[j] -= dq;
+                } else if chi_i > chi_j {
+                    let dq = (chi_i - chi_j) / topology.chi_plus[j];
+                    residual[j] += dq;
+                    residual[i] -= dq;
+                }
+            }
+        }
+        for (r, &frozen) in residual.iter_mut().zip(&topology.frozen) {
+            if frozen {
+                *r = 0.0;
+            }
+        }
+        residual
+    }
+
+    /// Newton iteration on `transfer_residual(q) = 0` with charge conserved per
+    /// connected component without frozen atoms; frozen atoms keep their seed
+    /// charge. `charge_bounds` are not enforced here (`validate` rejects them
+    /// in this mode). Returns `(steps, converged)`.
+    pub(crate) fn equalize_linearized<F>(&self, charges: &mut [f64], topology: &Topology, mut on_iteration: F) -> (usize, bool)
+    where
+        F: FnMut(usize, &[f64]),
+    {
+        let n = charges.len();
+        let tolerance = self.tolerance.unwrap_or(LINEARIZED_TOLERANCE);
+        let labels = component_labels(n, topology.pairs.iter().copied());
+
+        for step in 0..MAX_NEWTON_STEPS {
+            let residual = self.transfer_residual(charges, topology);
+            if self.convergence_norm.measure(&residual) < tolerance {
+                return (step, true);
+            }
+
+            let mut matrix = vec![vec![0.0; n]; n];
+            let mut probe = charges.to_vec();
+            for k in 0..n {
+                probe[k] = charges[k] + JACOBIAN_STEP;
+                let plus = self.transfer_residual(&probe, topology);
+                probe[k] = charges[k] - JACOBIAN_STEP;
+                let minus = self.transfer_residual(&probe, topology);
+                probe[k] = charges[k];
+                for i in 0..n {
+                    matrix[i][k] = (plus[i] - minus[i]) / (2.0 * JACOBIAN_STEP);
+                }
+            }
+
+            // Frozen rows are zero; they become δqᵢ = 0. The rows of a component
+            // without frozen atoms sum to zero, so one of them is replaced by the
+            // conservation constraint Σ δq = 0. A frozen atom exchanges charge
+            // with its neighbors without moving itself, so its component's total
+            // is not conserved, as in the iterative schedule.
+            let mut rhs: Vec<f64> = residual.iter().map(|r| -r).collect();
+            for i in (0..n).filter(|&i| topology.frozen[i]) {
+                matrix[i] = (0..n).map(|k| if k == i { 1.0 } else { 0.0 }).collect();
+                rhs[i] = 0.0;
+            }
+            for root in 0..n {
+                if labels[root] != root || (0..n).any(|i| labels[i] == root && topology.frozen[i]) { continue; }
+                for (i, row) in matrix[root].iter_mut().enumerate() {
+                    *row = if labels[i] == root { 1.0 } else { 0.0 };
+                }
+                rhs[root] = 0.0;
+            }
+
+            // A singular system falls back to a plain damped step.
+            let delta = solve_dense(matrix, rhs)
+                .unwrap_or_else(|| residual.iter().map(|r| self.damping * r).collect());
+            for (q, d) in charges.iter_mut().zip(delta) {
+                *q += d;
+            }
+            on_iteration(step + 1, charges);
+        }
+
+        let converged = self.convergence_norm.measure(&self.transfer_residual(charges, topology)) < tolerance;
+        (MAX_NEWTON_STEPS, converged)
+    }
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting.
+/// Returns `None` if the matrix is numerically singular.
+pub(crate) fn solve_dense(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&x, &y| a[x][col].abs().total_cmp(&a[y][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (offset, row) in lower.iter_mut().enumerate() {
+            let factor = row[col] / pivot_row[col];
+            if factor == 0.0 { continue; }
+            for (x, p) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * p;
+            }
+            b[col + 1 + offset] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
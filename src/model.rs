@@ -0,0 +1,21 @@
+use crate::parameters::GasteigerParams;
+
+/// Electronegativity as a function of partial charge, χ(q).
+///
+/// The solver only ever evaluates χ through this trait, so alternative
+/// functional forms (exponential, tabulated, ...) can be plugged in while
+/// keeping `GasteigerParams` as the per-atom data.
+pub trait ElectronegativityModel {
+    /// Electronegativity of an atom with parameters `params` carrying charge `q`.
+    fn electronegativity(&self, params: &GasteigerParams, q: f64) -> f64;
+}
+
+/// The standard Gasteiger-Marsili quadratic model, χ(q) = a + bq + cq².
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuadraticModel;
+
+impl ElectronegativityModel for QuadraticModel {
+    fn electronegativity(&self, p: &GasteigerParams, q: f64) -> f64 {
+        p.a + p.b * q + p.c * q * q
+    }
+}
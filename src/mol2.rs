@@ -0,0 +1,179 @@
+//! Reader for Tripos MOL2 files (requires the `io` feature).
+//!
+//! Only the `MOLECULE`, `ATOM` and `BOND` record types are read; everything
+//! else is skipped. Atom types (`C.ar`, `N.am`, `O.co2`, …) are mapped to an
+//! element and a hybridization through `parameters::atom_type_context`, so the
+//! file's own typing is kept: each `Mol2Atom` reports it via
+//! `GasteigerAtom::atom_type`, and `hybridizations` can be passed to
+//! `GasteigerSolver::compute_charges_with_hybridizations`. The charge column of
+//! MOL2 holds partial charges, which are ignored: every formal charge is 0
+//! except `N.4`, which is read as +1.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use crate::molecule::Bond;
+use crate::parameters::{atom_type_context, Hybridization};
+use crate::traits::GasteigerAtom;
+
+/// An atom read from a MOL2 stream, keeping the atom type from the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mol2Atom {
+    pub atomic_number: usize,
+    pub formal_charge: f32,
+    /// Atom type column as written in the file, e.g. `C.ar`.
+    pub atom_type: String,
+}
+
+impl GasteigerAtom for Mol2Atom {
+    fn atomic_number(&self) -> usize { self.atomic_number }
+    fn formal_charge(&self) -> f32 { self.formal_charge }
+    fn atom_type(&self) -> Option<&str> { Some(&self.atom_type) }
+}
+
+/// One molecule read from a MOL2 stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mol2Molecule {
+    pub name: String,
+    pub atoms: Vec<Mol2Atom>,
+    pub bonds: Vec<Bond>,
+    /// Hybridization implied by each atom's type.
+    pub hybridizations: Vec<Hybridization>,
+    /// Atom coordinates in Å.
+    pub coords: Vec<[f64; 3]>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Molecule,
+    Atom,
+    Bond,
+    Other,
+}
+
+/// Reads every molecule in a MOL2 stream.
+///
+/// Dummy atoms and lone pairs (`Du`, `LP`) get atomic number 0 and therefore act
+/// as spectators. Malformed atom or bond lines, unknown element symbols and bonds
+/// to undeclared atom ids yield an `io::ErrorKind::InvalidData` error naming the
+/// line. Bond type `nc` (not connected) is skipped; `ar` reads as 1.5, and `am`,
+/// `du` and `un` as 1.0.
+pub fn read_mol2<R: BufRead>(reader: R) -> io::Result<Vec<Mol2Molecule>> {
+    let mut molecules: Vec<Mol2Molecule> = Vec::new();
+    let mut ids: HashMap<String, usize> = HashMap::new();
+    let mut section = Section::Other;
+    let mut expect_name = false;
+
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(record) = trimmed.strip_prefix("@<TRIPOS>") {
+            section = match record {
+                "MOLECULE" => {
+                    molecules.push(Mol2Molecule {
+                        name: String::new(),
+                        atoms: Vec::new(),
+                        bonds: Vec::new(),
+                        hybridizations: Vec::new(),
+                        coords: Vec::new(),
+                    });
+                    ids.clear();
+                    expect_name = true;
+                    Section::Molecule
+                }
+                "ATOM" => Section::Atom,
+                "BOND" => Section::Bond,
+                _ => Section::Other,
+            };
+            continue;
+        }
+
+        let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_no + 1, what));
+        let Some(molecule) = molecules.last_mut() else { continue; };
+        let fields: Vec<&str> = trimmed.split_whitespace().collect();
+        match section {
+            Section::Molecule if expect_name => {
+                molecule.name = trimmed.to_string();
+                expect_name = false;
+            }
+            Section::Atom => {
+                if fields.len() < 6 {
+                    return Err(invalid("expected id, name, x, y, z and type"));
+                }
+                let mut xyz = [0.0; 3];
+                for (k, field) in fields[2..5].iter().enumerate() {
+                    xyz[k] = field.parse().map_err(|_| invalid("bad coordinate"))?;
+                }
+                let (atomic_number, hybridization) =
+                    sybyl_type(fields[5]).ok_or_else(|| invalid(&format!("unknown atom type {}", fields[5])))?;
+                let formal_charge = if fields[5] == "N.4" { 1.0 } else { 0.0 };
+                ids.insert(fields[0].to_string(), molecule.atoms.len());
+                molecule.atoms.push(Mol2Atom { atomic_number, formal_charge, atom_type: fields[5].to_string() });
+                molecule.hybridizations.push(hybridization);
+                molecule.coords.push(xyz);
+            }
+            Section::Bond => {
+                if fields.len() < 4 {
+                    return Err(invalid("expected id, origin, target and type"));
+                }
+                let index = |id: &str| ids.get(id).copied().ok_or_else(|| invalid(&format!("unknown atom id {}", id)));
+                let (i, j) = (index(fields[1])?, index(fields[2])?);
+                let order = match fields[3] {
+                    "1" | "am" | "du" | "un" => 1.0,
+                    "2" => 2.0,
+                    "3" => 3.0,
+                    "ar" => 1.5,
+                    "nc" => continue,
+                    other => return Err(invalid(&format!("unknown bond type {}", other))),
+                };
+                molecule.bonds.push(Bond::new(i, j, order));
+            }
+            _ => {}
+        }
+    }
+    Ok(molecules)
+}
+
+/// Element and hybridization for an atom type such as `C.ar` or `Cl`.
+///
+/// Types listed by `atom_type_context` (SYBYL and GAFF) are taken from that
+/// table. Any other SYBYL type is read as an element symbol and an optional
+/// geometry suffix, so `Si`, `Co.oh` or `Du` still resolve.
+fn sybyl_type(sybyl: &str) -> Option<(usize, Hybridization)> {
+    if let Some(context) = atom_type_context(sybyl) {
+        return Some(context);
+    }
+    let (symbol, suffix) = sybyl.split_once('.').unwrap_or((sybyl, ""));
+    let atomic_number = element_number(symbol)?;
+    let hybridization = match suffix {
+        "3" | "4" | "O" | "O2" | "oh" | "th" => Hybridization::Sp3,
+        "2" | "ar" | "am" | "pl3" | "co2" | "cat" => Hybridization::Sp2,
+        "1" => Hybridization::Sp,
+        _ => Hybridization::Default,
+    };
+    Some((atomic_number, hybridization))
+}
+
+const ELEMENT_SYMBOLS: [&str; 86] = [
+    "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl", "Ar", "K", "Ca",
+    "Sc", "Ti", "V", "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Ga", "Ge", "As", "Se", "Br", "Kr", "Rb", "Sr", "Y",
+    "Zr", "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In", "Sn", "Sb", "Te", "I", "Xe", "Cs", "Ba", "La", "Ce",
+    "Pr", "Nd", "Pm", "Sm", "Eu", "Gd", "Tb", "Dy", "Ho", "Er", "Tm", "Yb", "Lu", "Hf", "Ta", "W", "Re", "Os", "Ir",
+    "Pt", "Au", "Hg", "Tl", "Pb", "Bi", "Po", "At", "Rn",
+];
+
+/// Element symbol for an atomic number, if it is one of the first 86 elements.
+pub(crate) fn element_symbol(atomic_number: usize) -> Option<&'static str> {
+    ELEMENT_SYMBOLS.get(atomic_number.checked_sub(1)?).copied()
+}
+
+/// Atomic number for an element symbol; dummy atoms and lone pairs (`Du`, `LP`) map to 0.
+fn element_number(symbol: &str) -> Option<usize> {
+    if symbol == "Du" || symbol == "LP" {
+        return Some(0);
+    }
+    ELEMENT_SYMBOLS.iter().position(|&s| s == symbol).map(|i| i + 1)
+}
@@ -0,0 +1,428 @@
+//! Built-in atom and bond types plus helpers for assembling molecules.
+
+use std::collections::HashMap;
+
+use crate::error::SolverError;
+use crate::topology::component_labels;
+use crate::traits::{effective_order, GasteigerAtom, GasteigerBond};
+
+/// A plain atom record implementing `GasteigerAtom`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Atom {
+    pub atomic_number: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub formal_charge: f32,
+}
+
+impl Atom {
+    pub fn new(atomic_number: usize, formal_charge: f32) -> Self {
+        Self { atomic_number, formal_charge }
+    }
+}
+
+impl GasteigerAtom for Atom {
+    fn atomic_number(&self) -> usize { self.atomic_number }
+    fn formal_charge(&self) -> f32 { self.formal_charge }
+}
+
+/// Builds a `Vec<Atom>` from `(atomic_number, formal_charge)` tuples.
+///
+/// ```
+/// let water = gasteiger_rs::atoms![(8, 0.0), (1, 0.0), (1, 0.0)];
+/// assert_eq!(water[0], gasteiger_rs::Atom::new(8, 0.0));
+/// ```
+#[macro_export]
+macro_rules! atoms {
+    ($(($z:expr, $charge:expr)),* $(,)?) => {
+        vec![$($crate::molecule::Atom::new($z, $charge)),*]
+    };
+}
+
+/// Builds a `Vec<Bond>` from `(i, j, order)` tuples.
+///
+/// ```
+/// let water = gasteiger_rs::bonds![(0, 1, 1.0), (0, 2, 1.0)];
+/// assert_eq!(water[1], gasteiger_rs::Bond::new(0, 2, 1.0));
+/// ```
+#[macro_export]
+macro_rules! bonds {
+    ($(($i:expr, $j:expr, $order:expr)),* $(,)?) => {
+        vec![$($crate::molecule::Bond::new($i, $j, $order)),*]
+    };
+}
+
+/// A plain bond record implementing `GasteigerBond`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bond {
+    pub pair: (usize, usize),
+    pub order: f32,
+}
+
+impl Bond {
+    pub fn new(i: usize, j: usize, order: f32) -> Self {
+        Self { pair: (i, j), order }
+    }
+}
+
+impl GasteigerBond for Bond {
+    fn atom_indices(&self) -> (usize, usize) { self.pair }
+    fn bond_order(&self) -> f32 { self.order }
+}
+
+/// A bond packed into a single `u64` for memory-bound workloads.
+///
+/// Bits 0-23 and 24-47 hold the two atom indices, bits 48-55 the bond order in
+/// quarter units (so 1.5 is stored as 6). Indices are therefore limited to
+/// `PackedBond::MAX_INDEX` (~16.7M atoms) and orders to multiples of 0.25 up to 63.75.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PackedBond(pub u64);
+
+impl PackedBond {
+    /// Largest atom index representable in 24 bits.
+    pub const MAX_INDEX: usize = (1 << 24) - 1;
+
+    /// Packs a bond, or returns `None` if an index exceeds `MAX_INDEX` or the
+    /// order is negative, too large, or not a multiple of 0.25.
+    pub fn pack(i: usize, j: usize, order: f32) -> Option<Self> {
+        if i > Self::MAX_INDEX || j > Self::MAX_INDEX {
+            return None;
+        }
+        let code = order * 4.0;
+        if !(0.0..=255.0).contains(&code) || code.fract() != 0.0 {
+            return None;
+        }
+        Some(Self(i as u64 | (j as u64) << 24 | (code as u64) << 48))
+    }
+
+    /// Returns `(i, j, order)`.
+    pub fn unpack(self) -> (usize, usize, f32) {
+        let (i, j) = self.atom_indices();
+        (i, j, self.bond_order())
+    }
+}
+
+impl GasteigerBond for PackedBond {
+    fn atom_indices(&self) -> (usize, usize) {
+        let mask = Self::MAX_INDEX as u64;
+        ((self.0 & mask) as usize, (self.0 >> 24 & mask) as usize)
+    }
+    fn bond_order(&self) -> f32 { (self.0 >> 48 & 0xff) as f32 / 4.0 }
+}
+
+/// Concatenates two molecules into one system.
+///
+/// The atoms of `b` are appended after those of `a`, and the bond indices of `b`
+/// are offset by `a_atoms.len()` so they keep pointing at the same atoms.
+pub fn concat_molecules(
+    a_atoms: &[Atom],
+    a_bonds: &[Bond],
+    b_atoms: &[Atom],
+    b_bonds: &[Bond],
+) -> (Vec<Atom>, Vec<Bond>) {
+    let offset = a_atoms.len();
+    let atoms = a_atoms.iter().chain(b_atoms.iter()).copied().collect();
+    let bonds = a_bonds
+        .iter()
+        .copied()
+        .chain(b_bonds.iter().map(|b| Bond::new(b.pair.0 + offset, b.pair.1 + offset, b.order)))
+        .collect();
+    (atoms, bonds)
+}
+
+/// Removes the atoms listed in `to_remove` and renumbers the rest.
+///
+/// Returns the remaining atoms in their original order, the bonds between them
+/// with remapped indices (bonds touching a removed or out-of-range atom are
+/// dropped), and a map from old to new atom index in which removed atoms map to
+/// `usize::MAX`. Indices in `to_remove` that are out of range or repeated are
+/// ignored.
+pub fn remove_atoms(atoms: &[Atom], bonds: &[Bond], to_remove: &[usize]) -> (Vec<Atom>, Vec<Bond>, Vec<usize>) {
+    let mut removed = vec![false; atoms.len()];
+    for &i in to_remove.iter().filter(|&&i| i < atoms.len()) {
+        removed[i] = true;
+    }
+    let mut index_map = vec![usize::MAX; atoms.len()];
+    let mut kept = Vec::with_capacity(atoms.len());
+    for (old, atom) in atoms.iter().enumerate().filter(|&(i, _)| !removed[i]) {
+        index_map[old] = kept.len();
+        kept.push(*atom);
+    }
+    let new_index = |i: usize| index_map.get(i).copied().filter(|&n| n != usize::MAX);
+    let kept_bonds = bonds
+        .iter()
+        .filter_map(|b| Some(Bond::new(new_index(b.pair.0)?, new_index(b.pair.1)?, b.order)))
+        .collect();
+    (kept, kept_bonds, index_map)
+}
+
+/// Number of connected components, counting isolated atoms as their own component.
+/// Bonds referencing missing atoms are ignored.
+pub fn component_count<A, B>(atoms: &[A], bonds: &[B]) -> usize
+where
+    A: GasteigerAtom,
+    B: GasteigerBond,
+{
+    component_labels(atoms.len(), bonds.iter().map(|b| b.atom_indices()))
+        .iter()
+        .enumerate()
+        .filter(|&(i, &label)| label == i)
+        .count()
+}
+
+/// Formal charges implied by each atom's total bond order, for molecules with
+/// explicit hydrogens and Kekulé (or no) aromatic bonds.
+///
+/// The rules cover the common organic ions: nitrogen and phosphorus with four
+/// bond-order units are +1 and with two are −1; oxygen and sulfur with three are
+/// +1 and with one are −1; four-coordinate boron is −1; unbonded halogens are
+/// −1 and unbonded Li, Na and K are +1. Everything else, including every atom
+/// with an aromatic bond, is 0. The result can be copied into the atoms'
+/// formal charges before solving.
+///
+/// The inference is only as good as the input graph. Missing hydrogens read as
+/// anions (a bare `O` becomes −1), hypervalent P and S with five or six units
+/// stay at 0, three-valent carbon is left neutral because cation, anion and
+/// radical are indistinguishable, and aromatic 1.5 orders are not
+/// Kekulé-assigned, so a pyridinium nitrogen is not detected. Bonds to virtual
+/// sites and bonds referencing missing atoms are ignored.
+pub fn infer_formal_charges<A, B>(atoms: &[A], bonds: &[B]) -> Vec<f32>
+where
+    A: GasteigerAtom,
+    B: GasteigerBond,
+{
+    let mut valence = vec![0.0f32; atoms.len()];
+    let mut aromatic = vec![false; atoms.len()];
+    for bond in bonds {
+        let (i, j) = bond.atom_indices();
+        if i >= atoms.len() || j >= atoms.len() || atoms[i].is_virtual_site() || atoms[j].is_virtual_site() {
+            continue;
+        }
+        let order = effective_order(bond);
+        for k in [i, j] {
+            valence[k] += order;
+            aromatic[k] |= order.fract() != 0.0;
+        }
+    }
+    atoms
+        .iter()
+        .enumerate()
+        .map(|(k, atom)| {
+            if aromatic[k] || atom.is_virtual_site() {
+                return 0.0;
+            }
+            match (atom.atomic_number(), valence[k].round() as u32) {
+                (7 | 15, 4) | (8 | 16, 3) | (3 | 11 | 19, 0) => 1.0,
+                (7 | 15, 2) | (8 | 16, 1) | (5, 4) | (9 | 17 | 35 | 53, 0) => -1.0,
+                _ => 0.0,
+            }
+        })
+        .collect()
+}
+
+/// Single-bond covalent radius in Å (Cordero et al., 2008), for the elements
+/// with Gasteiger parameters.
+pub fn covalent_radius(atomic_number: usize) -> Option<f64> {
+    match atomic_number {
+        1 => Some(0.31),
+        5 => Some(0.84),
+        6 => Some(0.76),
+        7 => Some(0.71),
+        8 => Some(0.66),
+        9 => Some(0.57),
+        14 => Some(1.11),
+        15 => Some(1.07),
+        16 => Some(1.05),
+        17 => Some(1.02),
+        35 => Some(1.20),
+        53 => Some(1.39),
+        _ => None,
+    }
+}
+
+/// Distance cutoffs used by `bonds_from_coordinates`.
+///
+/// Two atoms are bonded when their distance is at most the cutoff for their
+/// element pair: the entry in `overrides` if present (keyed by the two atomic
+/// numbers in either order), otherwise the sum of their `covalent_radius`
+/// values plus `tolerance`. Pairs with no override and an element lacking a
+/// radius are never bonded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BondCutoffs {
+    pub tolerance: f64,
+    pub overrides: HashMap<(usize, usize), f64>,
+}
+
+impl Default for BondCutoffs {
+    fn default() -> Self {
+        Self { tolerance: 0.45, overrides: HashMap::new() }
+    }
+}
+
+impl BondCutoffs {
+    /// Bonding cutoff in Å for a pair of elements, if one is known.
+    pub fn cutoff(&self, a: usize, b: usize) -> Option<f64> {
+        let key = (a.min(b), a.max(b));
+        if let Some(&cutoff) = self.overrides.get(&key) {
+            return Some(cutoff);
+        }
+        Some(covalent_radius(a)? + covalent_radius(b)? + self.tolerance)
+    }
+}
+
+/// Infers single bonds from coordinates (Å) using element-pair distance cutoffs.
+///
+/// Every bond has order 1.0 and pairs are listed with `i < j`. The check is
+/// all-pairs, O(n²), which is fine for single molecules.
+pub fn bonds_from_coordinates<A: GasteigerAtom>(
+    atoms: &[A],
+    coords: &[[f64; 3]],
+    cutoffs: &BondCutoffs,
+) -> Result<Vec<Bond>, SolverError> {
+    if coords.len() != atoms.len() {
+        return Err(SolverError::AtomCountMismatch { expected: atoms.len(), found: coords.len() });
+    }
+    let mut bonds = Vec::new();
+    for i in 0..atoms.len() {
+        for j in i + 1..atoms.len() {
+            let Some(cutoff) = cutoffs.cutoff(atoms[i].atomic_number(), atoms[j].atomic_number()) else { continue; };
+            let d2: f64 = (0..3).map(|k| (coords[i][k] - coords[j][k]).powi(2)).sum();
+            if d2 <= cutoff * cutoff {
+                bonds.push(Bond::new(i, j, 1.0));
+            }
+        }
+    }
+    Ok(bonds)
+}
+
+/// Reorders a molecule canonically so equivalent inputs compare equal.
+///
+/// Atoms are ranked by a Morgan-style relaxation: the initial invariant is
+/// (atomic number, degree, formal charge), and each round refines a rank by the
+/// sorted (neighbor rank, bond order) pairs around the atom until the number of
+/// distinct ranks stops growing. Remaining ties (symmetry-equivalent atoms) are
+/// broken by promoting one tied atom and relaxing again.
+///
+/// Returns `(permutation, atoms, bonds)` where `permutation[k]` is the input index
+/// of canonical atom `k`. Bonds are renumbered with `i < j` and sorted;
+/// bonds referencing missing atoms are dropped.
+pub fn canonicalize<A, B>(atoms: &[A], bonds: &[B]) -> (Vec<usize>, Vec<Atom>, Vec<Bond>)
+where
+    A: GasteigerAtom,
+    B: GasteigerBond,
+{
+    let n = atoms.len();
+    let (mut ranks, neighbors) = morgan_ranks(atoms, bonds);
+    while let Some(tied) = lowest_tie(&ranks) {
+        let promoted: Vec<(usize, bool)> = ranks.iter().enumerate().map(|(i, &r)| (r, i != tied)).collect();
+        ranks = relax(dense_ranks(&promoted), &neighbors);
+    }
+
+    let mut permutation = vec![0; n];
+    for (old, &rank) in ranks.iter().enumerate() {
+        permutation[rank] = old;
+    }
+    let canonical_atoms = permutation
+        .iter()
+        .map(|&old| Atom::new(atoms[old].atomic_number(), atoms[old].formal_charge()))
+        .collect();
+    let mut canonical_bonds: Vec<Bond> = bonds
+        .iter()
+        .filter_map(|b| {
+            let (i, j) = b.atom_indices();
+            if i >= n || j >= n {
+                return None;
+            }
+            let (ri, rj) = (ranks[i], ranks[j]);
+            Some(Bond::new(ri.min(rj), ri.max(rj), effective_order(b)))
+        })
+        .collect();
+    canonical_bonds.sort_by(|x, y| x.pair.cmp(&y.pair).then(x.order.total_cmp(&y.order)));
+    (permutation, canonical_atoms, canonical_bonds)
+}
+
+/// Topological equivalence classes of the atoms, labelled `0..k`.
+///
+/// These are the ranks of `canonicalize`'s Morgan-style relaxation before any
+/// tie is broken, so two atoms share a label when their element, degree,
+/// formal charge and bond orders agree out to every neighbor shell (the six
+/// hydrogens of ethane, but not the CH2 and CH3 hydrogens of ethanol).
+/// Symmetry-equivalent atoms always share a label; in some highly
+/// regular graphs the refinement can also merge atoms no symmetry exchanges.
+pub fn equivalence_classes<A, B>(atoms: &[A], bonds: &[B]) -> Vec<usize>
+where
+    A: GasteigerAtom,
+    B: GasteigerBond,
+{
+    morgan_ranks(atoms, bonds).0
+}
+
+/// Relaxed ranks from the (atomic number, degree, formal charge) invariant,
+/// together with each atom's `(neighbor, bond order bits)` list.
+fn morgan_ranks<A, B>(atoms: &[A], bonds: &[B]) -> (Vec<usize>, Vec<Vec<(usize, u32)>>)
+where
+    A: GasteigerAtom,
+    B: GasteigerBond,
+{
+    let n = atoms.len();
+    let mut neighbors: Vec<Vec<(usize, u32)>> = vec![Vec::new(); n];
+    for bond in bonds {
+        let (i, j) = bond.atom_indices();
+        if i < n && j < n && i != j {
+            let order = effective_order(bond).to_bits();
+            neighbors[i].push((j, order));
+            neighbors[j].push((i, order));
+        }
+    }
+
+    let initial: Vec<(usize, usize, u32)> = atoms
+        .iter()
+        .enumerate()
+        .map(|(i, a)| (a.atomic_number(), neighbors[i].len(), a.formal_charge().to_bits()))
+        .collect();
+    (relax(dense_ranks(&initial), &neighbors), neighbors)
+}
+
+/// Dense ranks `0..k` of `keys`, equal keys sharing a rank.
+fn dense_ranks<K: Ord>(keys: &[K]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by(|&x, &y| keys[x].cmp(&keys[y]));
+    let mut ranks = vec![0; keys.len()];
+    for w in 1..order.len() {
+        let step = usize::from(keys[order[w]] != keys[order[w - 1]]);
+        ranks[order[w]] = ranks[order[w - 1]] + step;
+    }
+    ranks
+}
+
+/// Refines ranks by neighborhoods until the number of classes stops growing.
+fn relax(mut ranks: Vec<usize>, neighbors: &[Vec<(usize, u32)>]) -> Vec<usize> {
+    let classes = |r: &[usize]| r.iter().max().map_or(0, |m| m + 1);
+    loop {
+        let keys: Vec<(usize, Vec<(usize, u32)>)> = neighbors
+            .iter()
+            .enumerate()
+            .map(|(i, nbrs)| {
+                let mut env: Vec<(usize, u32)> = nbrs.iter().map(|&(j, o)| (ranks[j], o)).collect();
+                env.sort_unstable();
+                (ranks[i], env)
+            })
+            .collect();
+        let refined = dense_ranks(&keys);
+        if classes(&refined) == classes(&ranks) {
+            return refined;
+        }
+        ranks = refined;
+    }
+}
+
+/// The first atom holding the lowest rank shared by more than one atom.
+fn lowest_tie(ranks: &[usize]) -> Option<usize> {
+    let mut counts = vec![0usize; ranks.len()];
+    for &r in ranks {
+        counts[r] += 1;
+    }
+    let rank = (0..counts.len()).find(|&r| counts[r] > 1)?;
+    ranks.iter().position(|&r| r == rank)
+}
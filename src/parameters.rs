@@ -1,11 +1,25 @@
+use std::collections::HashMap;
+use std::fmt;
+
 /// Parameters for Gasteiger electronegativity (a + bq + cq^2).
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct GasteigerParams {
     pub a: f64,
     pub b: f64,
     pub c: f64,
 }
 
+impl GasteigerParams {
+    /// Linear interpolation of a, b and c: `t = 0` gives `self`, `t = 1` gives `other`.
+    pub fn lerp(&self, other: &Self, t: f64) -> Self {
+        Self {
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+            c: self.c + (other.c - self.c) * t,
+        }
+    }
+}
+
 /// Supported hybridizations for different elements.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Hybridization {
@@ -15,6 +29,113 @@ pub enum Hybridization {
     Default,
 }
 
+impl Hybridization {
+    /// Every hybridization state, in declaration order.
+    pub const ALL: [Hybridization; 4] = [
+        Hybridization::Sp3,
+        Hybridization::Sp2,
+        Hybridization::Sp,
+        Hybridization::Default,
+    ];
+}
+
+/// A source of Gasteiger parameters, e.g. the built-in table, a database or a fit.
+///
+/// Implemented for `BuiltinParams`, for `HashMap<(usize, Hybridization), GasteigerParams>`
+/// and for any `Fn(usize, Hybridization) -> Option<GasteigerParams>` closure. The
+/// solver's fallback chain (guessed → Sp3 → Default) queries the provider once per
+/// step, so a provider only needs to answer for the states it knows about.
+pub trait ParamProvider {
+    fn params(&self, atomic_number: usize, hyb: Hybridization) -> Option<GasteigerParams>;
+}
+
+/// The built-in Gasteiger-Marsili table, as returned by `get_params`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuiltinParams;
+
+impl ParamProvider for BuiltinParams {
+    fn params(&self, atomic_number: usize, hyb: Hybridization) -> Option<GasteigerParams> {
+        get_params(atomic_number, hyb)
+    }
+}
+
+impl ParamProvider for HashMap<(usize, Hybridization), GasteigerParams> {
+    fn params(&self, atomic_number: usize, hyb: Hybridization) -> Option<GasteigerParams> {
+        self.get(&(atomic_number, hyb)).copied()
+    }
+}
+
+impl<F> ParamProvider for F
+where
+    F: Fn(usize, Hybridization) -> Option<GasteigerParams>,
+{
+    fn params(&self, atomic_number: usize, hyb: Hybridization) -> Option<GasteigerParams> {
+        self(atomic_number, hyb)
+    }
+}
+
+impl fmt::Debug for dyn ParamProvider + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ParamProvider")
+    }
+}
+
+/// An explicit table of parameters, e.g. a snapshot of the built-in set or an
+/// alternative parameterization. Usable directly as a `ParamProvider`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParameterSet {
+    pub entries: HashMap<(usize, Hybridization), GasteigerParams>,
+}
+
+impl ParameterSet {
+    /// Every entry of the built-in table. Wildcard rows (e.g. halogens, which use
+    /// one set for any hybridization) appear once per hybridization.
+    pub fn builtin() -> Self {
+        let entries = (1..=MAX_ATOMIC_NUMBER)
+            .flat_map(|z| Hybridization::ALL.into_iter().map(move |h| (z, h)))
+            .filter_map(|(z, h)| get_params(z, h).map(|p| ((z, h), p)))
+            .collect();
+        Self { entries }
+    }
+}
+
+impl ParamProvider for ParameterSet {
+    fn params(&self, atomic_number: usize, hyb: Hybridization) -> Option<GasteigerParams> {
+        self.entries.params(atomic_number, hyb)
+    }
+}
+
+/// Lists the entries that differ between two parameter sets, sorted by atomic
+/// number and then hybridization (`Hybridization::ALL` order).
+///
+/// Each item is `(atomic_number, hybridization, in_a, in_b)`; one side is `None`
+/// when the entry exists in only one set.
+pub fn diff_param_sets(
+    a: &ParameterSet,
+    b: &ParameterSet,
+) -> Vec<(usize, Hybridization, Option<GasteigerParams>, Option<GasteigerParams>)> {
+    let mut keys: Vec<(usize, Hybridization)> = a.entries.keys().chain(b.entries.keys()).copied().collect();
+    let hyb_rank = |h: Hybridization| Hybridization::ALL.iter().position(|&x| x == h);
+    keys.sort_by_key(|&(z, h)| (z, hyb_rank(h)));
+    keys.dedup();
+    keys.into_iter()
+        .filter_map(|(z, h)| {
+            let (pa, pb) = (a.entries.get(&(z, h)).copied(), b.entries.get(&(z, h)).copied());
+            (pa != pb).then_some((z, h, pa, pb))
+        })
+        .collect()
+}
+
+/// Highest atomic number considered when scanning the parameter table.
+const MAX_ATOMIC_NUMBER: usize = 118;
+
+/// Sorted atomic numbers that have at least one parameter entry.
+pub fn supported_elements() -> Vec<usize> {
+    (1..=MAX_ATOMIC_NUMBER)
+        .filter(|&z| Hybridization::ALL.iter().any(|&h| get_params(z, h).is_some()))
+        .collect()
+}
+
 /// Helper function to get electronegativity parameters for an atom.
 pub fn get_params(atomic_number: usize, hybridization: Hybridization) -> Option<GasteigerParams> {
     match (atomic_number, hybridization) {
@@ -57,3 +178,105 @@ pub fn get_params(atomic_number: usize, hybridization: Hybridization) -> Option<
         _ => None, // Fallback for unsupported elements/states
     }
 }
+
+/// SYBYL (`C.3`, `N.ar`, …) and GAFF (`c3`, `ca`, …) atom types with the
+/// element and hybridization they stand for. Aromatic carbon and nitrogen map
+/// to Sp2, as in the original Gasteiger-Marsili typing.
+const ATOM_TYPES: [(&str, usize, Hybridization); 56] = [
+    // SYBYL
+    ("H", 1, Hybridization::Default),
+    ("C.3", 6, Hybridization::Sp3),
+    ("C.2", 6, Hybridization::Sp2),
+    ("C.ar", 6, Hybridization::Sp2),
+    ("C.cat", 6, Hybridization::Sp2),
+    ("C.1", 6, Hybridization::Sp),
+    ("N.3", 7, Hybridization::Sp3),
+    ("N.4", 7, Hybridization::Sp3),
+    ("N.2", 7, Hybridization::Sp2),
+    ("N.ar", 7, Hybridization::Sp2),
+    ("N.am", 7, Hybridization::Sp2),
+    ("N.pl3", 7, Hybridization::Sp2),
+    ("N.1", 7, Hybridization::Sp),
+    ("O.3", 8, Hybridization::Sp3),
+    ("O.2", 8, Hybridization::Sp2),
+    ("O.co2", 8, Hybridization::Sp2),
+    ("F", 9, Hybridization::Default),
+    ("P.3", 15, Hybridization::Sp3),
+    ("S.3", 16, Hybridization::Sp3),
+    ("S.2", 16, Hybridization::Sp2),
+    ("S.O", 16, Hybridization::Sp3),
+    ("S.O2", 16, Hybridization::Sp3),
+    ("Cl", 17, Hybridization::Default),
+    ("Br", 35, Hybridization::Default),
+    ("I", 53, Hybridization::Default),
+    // GAFF
+    ("hc", 1, Hybridization::Default),
+    ("ha", 1, Hybridization::Default),
+    ("ho", 1, Hybridization::Default),
+    ("hn", 1, Hybridization::Default),
+    ("hs", 1, Hybridization::Default),
+    ("c3", 6, Hybridization::Sp3),
+    ("c2", 6, Hybridization::Sp2),
+    ("c", 6, Hybridization::Sp2),
+    ("ca", 6, Hybridization::Sp2),
+    ("c1", 6, Hybridization::Sp),
+    ("n3", 7, Hybridization::Sp3),
+    ("n4", 7, Hybridization::Sp3),
+    ("n", 7, Hybridization::Sp2),
+    ("na", 7, Hybridization::Sp2),
+    ("nb", 7, Hybridization::Sp2),
+    ("nh", 7, Hybridization::Sp2),
+    ("n2", 7, Hybridization::Sp2),
+    ("n1", 7, Hybridization::Sp),
+    ("oh", 8, Hybridization::Sp3),
+    ("os", 8, Hybridization::Sp3),
+    ("o", 8, Hybridization::Sp2),
+    ("f", 9, Hybridization::Default),
+    ("p5", 15, Hybridization::Sp3),
+    ("sh", 16, Hybridization::Sp3),
+    ("ss", 16, Hybridization::Sp3),
+    ("s", 16, Hybridization::Sp2),
+    ("s4", 16, Hybridization::Sp3),
+    ("s6", 16, Hybridization::Sp3),
+    ("cl", 17, Hybridization::Default),
+    ("br", 35, Hybridization::Default),
+    ("i", 53, Hybridization::Default),
+];
+
+/// Element and hybridization for a SYBYL or GAFF atom type; `None` for types
+/// not in the table. Matching is exact and case-sensitive: SYBYL types start
+/// with a capitalized element symbol, GAFF types are all lowercase.
+pub fn atom_type_context(atom_type: &str) -> Option<(usize, Hybridization)> {
+    ATOM_TYPES.iter().find(|&&(t, _, _)| t == atom_type).map(|&(_, z, h)| (z, h))
+}
+
+/// Parameters for a SYBYL or GAFF atom type, the type-based counterpart of
+/// `get_params`.
+pub fn get_params_by_type(atom_type: &str) -> Option<GasteigerParams> {
+    atom_type_context(atom_type).and_then(|(z, h)| get_params(z, h))
+}
+
+/// Positive charge bias along the R–X axis for heavy halogens (σ-hole).
+///
+/// These are empirical offsets, not part of the Gasteiger-Marsili set; they are
+/// only used by `GasteigerSolver::sigma_holes`.
+pub fn sigma_hole_bias(atomic_number: usize) -> Option<f64> {
+    match atomic_number {
+        35 => Some(0.05), // Bromine
+        53 => Some(0.08), // Iodine
+        _ => None,
+    }
+}
+
+/// Suggested hydrogen parameters keyed by the bonded heavy atom (O = 8, N = 7),
+/// for use as `GasteigerSolver::polar_hydrogen_params`.
+///
+/// These are empirical adjustments, not part of the Gasteiger-Marsili set: the
+/// generic hydrogen `a` is lowered so that hydrogens on strongly polar bonds give
+/// up somewhat more charge. Hydrogens on other elements keep the generic entry.
+pub fn polar_hydrogen_params() -> HashMap<usize, GasteigerParams> {
+    HashMap::from([
+        (8, GasteigerParams { a: 6.95, b: 6.24, c: -0.56 }), // H on oxygen
+        (7, GasteigerParams { a: 7.05, b: 6.24, c: -0.56 }), // H on nitrogen
+    ])
+}
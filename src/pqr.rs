@@ -0,0 +1,84 @@
+//! Writer for PQR files (requires the `io` feature).
+//!
+//! PQR is PDB with the occupancy and temperature-factor columns replaced by
+//! the partial charge (e) and the atomic radius (Å), as read by APBS, PDB2PQR
+//! and similar electrostatics tools. Every atom is written as an `ATOM` record
+//! of a single residue `MOL 1`, named after its element and its running count
+//! within that element (`O1`, `H1`, `H2`, …), followed by `TER` and `END`.
+
+use std::io::{self, Write};
+
+use crate::mol2::element_symbol;
+use crate::traits::PositionedAtom;
+
+/// Radius written for elements without a tabulated van der Waals radius.
+pub const FALLBACK_RADIUS: f64 = 1.5;
+
+/// Van der Waals radius in Å (Bondi, 1964) for the elements with Gasteiger
+/// parameters.
+pub fn vdw_radius(atomic_number: usize) -> Option<f64> {
+    match atomic_number {
+        1 => Some(1.20),
+        5 => Some(1.92),
+        6 => Some(1.70),
+        7 => Some(1.55),
+        8 => Some(1.52),
+        9 => Some(1.47),
+        14 => Some(2.10),
+        15 => Some(1.80),
+        16 => Some(1.80),
+        17 => Some(1.75),
+        35 => Some(1.85),
+        53 => Some(1.98),
+        _ => None,
+    }
+}
+
+/// Writes `atoms` with their `charges` as PQR records.
+///
+/// `radii[i]` is used when present, finite and not negative. Otherwise virtual
+/// sites get radius 0 (they carry charge but no volume, like the TIP4P M site)
+/// and real atoms get their `vdw_radius`, or `FALLBACK_RADIUS` if the element
+/// has none. Atoms with an unknown atomic number are named `X`. Fails with
+/// `io::ErrorKind::InvalidInput` if `charges` and `atoms` differ in length.
+pub fn write_pqr<W, A>(mut writer: W, atoms: &[A], charges: &[f64], radii: &[f64]) -> io::Result<()>
+where
+    W: Write,
+    A: PositionedAtom,
+{
+    if charges.len() != atoms.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} charges for {} atoms", charges.len(), atoms.len()),
+        ));
+    }
+    let mut counts = std::collections::HashMap::new();
+    for (i, (atom, &charge)) in atoms.iter().zip(charges).enumerate() {
+        let symbol = element_symbol(atom.atomic_number()).unwrap_or("X");
+        let count = counts.entry(symbol).or_insert(0usize);
+        *count += 1;
+        let mut name = format!("{}{}", symbol, count);
+        name.truncate(4);
+        let radius = radii
+            .get(i)
+            .copied()
+            .filter(|r| r.is_finite() && *r >= 0.0)
+            .unwrap_or_else(|| {
+                if atom.is_virtual_site() { 0.0 } else { vdw_radius(atom.atomic_number()).unwrap_or(FALLBACK_RADIUS) }
+            });
+        let [x, y, z] = atom.position();
+        writeln!(
+            writer,
+            "ATOM  {:>5} {:<4} MOL     1    {:>8.3}{:>8.3}{:>8.3} {:>7.4} {:>6.4}",
+            i + 1,
+            name,
+            x,
+            y,
+            z,
+            charge,
+            radius
+        )?;
+    }
+    writeln!(writer, "TER")?;
+    writeln!(writer, "END")
+}
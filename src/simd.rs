@@ -0,0 +1,132 @@
+//! Single-precision fast path with the electronegativity polynomial evaluated
+//! eight bonds at a time.
+//!
+//! Parameters, charges and transfers are all `f32`. That halves memory traffic
+//! and doubles the SIMD lane count, at the cost of roughly 1e-6 relative error
+//! per operation; after the default six iterations charges typically differ
+//! from the `f64` solver by less than 1e-5 e. Both paths always use the
+//! quadratic model and the geometric damping schedule; `tolerance`, adaptive
+//! damping and the linearized mode are not applied. Frozen atoms are honored.
+
+use wide::{CmpGt, f32x8};
+
+use crate::model::QuadraticModel;
+use crate::solver::GasteigerSolver;
+use crate::traits::{GasteigerAtom, GasteigerBond};
+
+const LANES: usize = 8;
+
+/// Per-atom parameters in structure-of-arrays form.
+struct Table {
+    a: Vec<f32>,
+    b: Vec<f32>,
+    c: Vec<f32>,
+    chi_plus: Vec<f32>,
+    pairs: Vec<(usize, usize)>,
+}
+
+impl Table {
+    fn chi(&self, atom: usize, q: f32) -> f32 {
+        self.a[atom] + q * (self.b[atom] + self.c[atom] * q)
+    }
+
+    /// Signed charge moving onto `i` across the bond `(i, j)`.
+    fn transfer(&self, i: usize, j: usize, charges: &[f32], scale: f32) -> f32 {
+        let (chi_i, chi_j) = (self.chi(i, charges[i]), self.chi(j, charges[j]));
+        let denom = if chi_j > chi_i { self.chi_plus[i] } else { self.chi_plus[j] };
+        scale * (chi_j - chi_i) / denom
+    }
+}
+
+impl GasteigerSolver<QuadraticModel> {
+    /// Scalar single-precision solve, the reference for `compute_charges_simd`.
+    pub fn compute_charges_f32<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<f32>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let table = self.table(atoms, bonds);
+        self.iterate(atoms, &table, |charges, scale, delta| {
+            for &(i, j) in &table.pairs {
+                let dq = table.transfer(i, j, charges, scale);
+                delta[i] += dq;
+                delta[j] -= dq;
+            }
+        })
+    }
+
+    /// Single-precision solve evaluating χ for eight bonds per instruction.
+    pub fn compute_charges_simd<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<f32>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let table = self.table(atoms, bonds);
+        self.iterate(atoms, &table, |charges, scale, delta| {
+            let chunks = table.pairs.chunks_exact(LANES);
+            for &(i, j) in chunks.remainder() {
+                let dq = table.transfer(i, j, charges, scale);
+                delta[i] += dq;
+                delta[j] -= dq;
+            }
+            for chunk in chunks {
+                let gather = |f: &dyn Fn(usize, usize) -> f32| {
+                    f32x8::from(std::array::from_fn::<f32, LANES, _>(|l| f(chunk[l].0, chunk[l].1)))
+                };
+                let q_i = gather(&|i, _| charges[i]);
+                let q_j = gather(&|_, j| charges[j]);
+                let chi_i = gather(&|i, _| table.a[i]) + q_i * (gather(&|i, _| table.b[i]) + gather(&|i, _| table.c[i]) * q_i);
+                let chi_j = gather(&|_, j| table.a[j]) + q_j * (gather(&|_, j| table.b[j]) + gather(&|_, j| table.c[j]) * q_j);
+                let denom = chi_j.cmp_gt(chi_i).blend(gather(&|i, _| table.chi_plus[i]), gather(&|_, j| table.chi_plus[j]));
+                let dq = (f32x8::splat(scale) * (chi_j - chi_i) / denom).to_array();
+                for (l, &(i, j)) in chunk.iter().enumerate() {
+                    delta[i] += dq[l];
+                    delta[j] -= dq[l];
+                }
+            }
+        })
+    }
+
+    fn table<A, B>(&self, atoms: &[A], bonds: &[B]) -> Table
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let topology = self.prepare(atoms, bonds);
+        let field = |f: fn(&crate::parameters::GasteigerParams) -> f64| {
+            topology.params.iter().map(|p| p.as_ref().map_or(0.0, |p| f(p) as f32)).collect()
+        };
+        Table {
+            a: field(|p| p.a),
+            b: field(|p| p.b),
+            c: field(|p| p.c),
+            chi_plus: topology.chi_plus.iter().map(|&x| x as f32).collect(),
+            pairs: topology.pairs.clone(),
+        }
+    }
+
+    /// Runs the damped schedule, letting `accumulate` fill in each iteration's deltas.
+    fn iterate<A, F>(&self, atoms: &[A], table: &Table, mut accumulate: F) -> Vec<f32>
+    where
+        A: GasteigerAtom,
+        F: FnMut(&[f32], f32, &mut [f32]),
+    {
+        let mut charges: Vec<f32> = atoms.iter().map(|a| a.formal_charge()).collect();
+        if table.pairs.is_empty() {
+            return charges;
+        }
+        let frozen: Vec<bool> = atoms.iter().map(|a| a.is_frozen()).collect();
+        let mut scale = 1.0f32;
+        for _ in 0..self.iterations {
+            let mut delta = vec![0.0f32; charges.len()];
+            accumulate(&charges, scale, &mut delta);
+            for ((q, d), &fixed) in charges.iter_mut().zip(delta).zip(&frozen) {
+                if !fixed {
+                    *q += d;
+                }
+            }
+            scale *= self.damping as f32;
+        }
+        charges
+    }
+}
@@ -1,103 +1,1896 @@
-use crate::traits::{GasteigerAtom, GasteigerBond};
-use crate::parameters::{Hybridization, get_params, GasteigerParams};
+use std::collections::HashMap;
+use std::sync::Arc;
 
-pub struct GasteigerSolver {
+use crate::analysis::round_charges;
+use crate::electrostatics::electrostatic_potential_at;
+use crate::error::SolverError;
+use crate::molecule::{equivalence_classes, Atom, Bond};
+use crate::topology::{component_labels, find_inconsistent_aromatic_ring};
+use crate::traits::{effective_order, perceived_order, BondKind, BondOrder, GasteigerAtom, GasteigerBond, IdentifiedAtom, ResolutionObserver};
+use crate::parameters::{atom_type_context, BuiltinParams, Hybridization, ParamProvider, sigma_hole_bias, GasteigerParams};
+use crate::model::{ElectronegativityModel, QuadraticModel};
+
+/// Damping schedule applied to the per-bond charge transfers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DampingMode {
+    /// Transfers in iteration `k` are scaled by `damping^k`.
+    #[default]
+    Geometric,
+    /// Geometric schedule, plus an extra factor of `ADAPTIVE_PENALTY` applied to
+    /// an atom each time the sign of its charge update flips between iterations.
+    /// A bond uses the stronger damping of its two atoms, so charge is conserved.
+    Adaptive,
+    /// Anderson mixing over the last `depth` iterates of the undamped map
+    /// `q ← q + damping · r(q)`, where `r` is the full per-atom transfer. This
+    /// converges to the fully equalized fixed point (like `SolveMode::Linearized`)
+    /// rather than the damped 6-step result, and needs a `tolerance` and a larger
+    /// `iterations` budget to be useful. Falls back to the plain damped step
+    /// whenever the mixing system is singular.
+    Anderson { depth: usize },
+}
+
+/// Number of iterations used by `GasteigerSolver::default()`.
+pub const DEFAULT_ITERATIONS: usize = 6;
+
+/// Geometric damping factor used by `GasteigerSolver::default()`.
+pub const DEFAULT_DAMPING: f64 = 0.5;
+
+/// Extra damping applied to an oscillating atom under `DampingMode::Adaptive`.
+pub const ADAPTIVE_PENALTY: f64 = 0.25;
+
+/// Largest formal-charge magnitude accepted by `GasteigerSolver::validate`.
+pub const MAX_FORMAL_CHARGE: f32 = 10.0;
+
+/// Formal-charge perturbation used by `GasteigerSolver::charge_sensitivity`.
+pub const SENSITIVITY_STEP: f64 = 1e-4;
+
+/// Net bond transfers smaller than this are reported as no flow by `charge_flow`.
+pub const FLOW_THRESHOLD: f64 = 1e-9;
+
+/// Boltzmann constant in kcal/(mol·K), used by `boltzmann_average_charges`.
+pub const BOLTZMANN_KCAL: f64 = 0.001_987_204_1;
+
+/// Optional down-weighting of per-bond transfers at highly connected atoms.
+///
+/// Each bond's transfer is divided by `f(d)`, where `d` is the larger degree
+/// (number of parameterized neighbors) of its two atoms. Only the iterative
+/// schedule applies it; `SolveMode::Linearized` ignores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DegreeNormalization {
+    /// `f(d) = 1`: the classic, unweighted transfer.
+    #[default]
+    None,
+    /// `f(d) = d`.
+    Degree,
+    /// `f(d) = √d`, a milder correction.
+    SqrtDegree,
+}
+
+/// Norm of the per-atom charge update compared against `tolerance`.
+///
+/// For `n` atoms the two are related by `RMS ≤ max ≤ √n · RMS`, so at the same
+/// tolerance `L2` never stops later than `LInfinity` and, on a large molecule
+/// with a few slowly settling atoms, stops considerably earlier. A tolerance
+/// tuned for `LInfinity` bounds every atom's update; an `L2` tolerance only
+/// bounds the average one and usually wants to be smaller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConvergenceNorm {
+    /// Largest absolute update over all atoms.
+    #[default]
+    LInfinity,
+    /// Root mean square of the updates over all atoms, spectators included.
+    L2,
+}
+
+impl ConvergenceNorm {
+    /// Applies the norm to `deltas`; zero for an empty slice.
+    pub fn measure(self, deltas: &[f64]) -> f64 {
+        match self {
+            ConvergenceNorm::LInfinity => deltas.iter().fold(0.0, |m, d| m.max(d.abs())),
+            ConvergenceNorm::L2 if deltas.is_empty() => 0.0,
+            ConvergenceNorm::L2 => (deltas.iter().map(|d| d * d).sum::<f64>() / deltas.len() as f64).sqrt(),
+        }
+    }
+}
+
+/// Algorithm used to equalize charges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SolveMode {
+    /// The classic damped Gasteiger-Marsili iteration.
+    #[default]
+    Iterative,
+    /// Newton's method on the fully equalized fixed point, where the undamped
+    /// per-atom transfer vanishes. Each step solves a dense linear system
+    /// (O(n³)), so this suits small molecules; it converges quadratically and
+    /// reaches the fixed point to near machine precision, whereas the damped
+    /// iteration stops short of full equalization by design. Results therefore
+    /// match an undamped iteration run to convergence, not the 6-step default.
+    /// `charge_bounds` are ignored in this mode; `validate` rejects molecules
+    /// with bounded atoms.
+    Linearized,
+}
+
+/// Charges together with convergence information from a solve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChargeResult {
+    pub charges: Vec<f64>,
+    /// Number of iterations actually performed.
+    pub iterations: usize,
+    /// Whether the charge update, measured by `convergence_norm`, fell below
+    /// `tolerance`. Always `false` when no tolerance is set.
+    pub converged: bool,
+    /// Indices of bonds flagged by `GasteigerBond::is_periodic_image`.
+    pub periodic_bonds: Vec<usize>,
+    /// Parameterized atoms whose every bond leads to an unparameterized atom
+    /// (e.g. a carbon bound only to Pd). They take part in no transfer, so their
+    /// charge is just the formal charge and should be treated as unreliable.
+    /// The remaining parameterized atoms are still equalized normally.
+    pub orphaned_atoms: Vec<usize>,
+    /// Atoms flagged by `GasteigerAtom::is_virtual_site`. Their charges are
+    /// included in `charges`, so the total still matches the formal-charge sum.
+    pub virtual_sites: Vec<usize>,
+}
+
+/// Charge-independent data for one molecule: resolved parameters, the cached
+/// χ(+1) denominators, and the bonds between parameterized atoms.
+#[derive(Debug, Clone)]
+pub(crate) struct Topology {
+    pub(crate) params: Vec<Option<GasteigerParams>>,
+    pub(crate) chi_plus: Vec<f64>,
+    pub(crate) pairs: Vec<(usize, usize)>,
+    /// Per-atom `(min, max)` charge bounds from `charge_bounds`.
+    pub(crate) bounds: Vec<Option<(f64, f64)>>,
+    /// Atoms whose charge no solve mode may update.
+    pub(crate) frozen: Vec<bool>,
+    /// Per-atom constant added to χ during the iteration (zero by default).
+    pub(crate) chi_offsets: Vec<f64>,
+}
+
+/// Sign and unit conversion applied to returned charges.
+///
+/// Output charges are `sign · scale · q` with `q` in elementary charges; e.g.
+/// `scale: 1.602176634e-19` gives coulombs. The default is the identity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputConvention {
+    /// `1.0` keeps the usual sign, `-1.0` inverts it.
+    pub sign: f64,
+    pub scale: f64,
+}
+
+impl Default for OutputConvention {
+    fn default() -> Self {
+        Self { sign: 1.0, scale: 1.0 }
+    }
+}
+
+/// Anisotropic charge estimate for a singly bonded heavy halogen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SigmaHole {
+    /// Index of the halogen atom.
+    pub atom: usize,
+    /// The usual isotropic Gasteiger charge of the halogen.
+    pub isotropic_charge: f64,
+    /// Charge seen along the R–X axis, beyond the halogen.
+    pub axial_charge: f64,
+    /// Unit vector pointing from the bonded atom through the halogen.
+    pub direction: [f64; 3],
+}
+
+/// How atoms of elements without parameters are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UnknownElementPolicy {
+    /// Reject the molecule. Enforced by the checked APIs (`validate`,
+    /// `compute_charges_checked`); the unchecked APIs behave like `Spectator`.
+    Error,
+    /// Keep the atom at its formal charge and skip its bonds.
+    #[default]
+    Spectator,
+    /// Use the given parameters for every unparameterized element.
+    GenericFallback(GasteigerParams),
+}
+
+/// Bond-order cut-offs used when perceiving hybridization from multiple bonds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BondOrderThresholds {
+    /// A bond at or above this order makes the atom Sp2 (covers aromatic 1.5).
+    pub aromatic: f32,
+    /// A bond at or above this order is a true double bond; two of them on
+    /// carbon or nitrogen make the atom Sp.
+    pub double: f32,
+    /// A bond at or above this order makes the atom Sp.
+    pub triple: f32,
+}
+
+impl Default for BondOrderThresholds {
+    fn default() -> Self {
+        Self { aromatic: 1.25, double: 1.75, triple: 2.5 }
+    }
+}
+
+impl BondOrderThresholds {
+    /// These thresholds as `(min_order, hybridization)` rules, highest first.
+    pub fn rules(&self) -> [(f32, Hybridization); 2] {
+        [(self.triple, Hybridization::Sp), (self.aromatic, Hybridization::Sp2)]
+    }
+}
+
+/// Per-element bond-order thresholds for hybridization perception.
+///
+/// Atoms with no bond at or above `aromatic` fall back to the neighbor-count
+/// rules. Hypervalent sulfur (more than two neighbors) always uses them.
+///
+/// Perception only looks at each atom's own bonds, never at ring membership, so
+/// fused ring systems need no special handling: a bridgehead atom shared by two
+/// aromatic rings (naphthalene C4a, indole C3a) is classified once from its
+/// three bonds, exactly like any other ring atom.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HybridizationThresholds {
+    pub carbon: BondOrderThresholds,
+    pub nitrogen: BondOrderThresholds,
+    pub oxygen: BondOrderThresholds,
+    pub sulfur: BondOrderThresholds,
+}
+
+/// Gasteiger-Marsili charge solver and its configuration.
+///
+/// The solver holds no interior mutability: every method takes `&self` and keeps
+/// its working state local to the call. It is therefore `Send + Sync` whenever
+/// the model `M` is (always true for `QuadraticModel`), and one configured
+/// solver can be shared across threads, e.g. behind an `Arc`.
+#[derive(Debug, Clone)]
+pub struct GasteigerSolver<M = QuadraticModel> {
     pub iterations: usize,
     pub damping: f64,
+    /// Electronegativity model used to evaluate χ(q).
+    pub model: M,
+    pub damping_mode: DampingMode,
+    /// Stop early once the per-atom charge update, measured by
+    /// `convergence_norm`, drops below this value. `None` always runs the full
+    /// `iterations`.
+    pub tolerance: Option<f64>,
+    /// How the charge update is reduced to one number for the `tolerance` check;
+    /// `LInfinity` (the largest update) by default.
+    pub convergence_norm: ConvergenceNorm,
+    pub hybridization_thresholds: HybridizationThresholds,
+    /// Blend Sp3 and Sp2 parameters for atoms whose highest bond order lies
+    /// strictly between 1 and 2, weighting Sp2 by `order - 1`.
+    pub blend_fractional_orders: bool,
+    pub unknown_elements: UnknownElementPolicy,
+    /// Hybridization assumed for elements the perceiver has no rules for
+    /// (anything other than C, N, O, P and S). Unlisted elements use `Default`.
+    pub element_hybridizations: HashMap<usize, Hybridization>,
+    pub mode: SolveMode,
+    /// Round the final charges to this many decimals with `analysis::round_charges`.
+    /// Applied only to the returned output, never between iterations.
+    pub output_decimals: Option<u32>,
+    /// Source of parameters; `BuiltinParams` by default.
+    pub param_provider: Arc<dyn ParamProvider + Send + Sync>,
+    /// Make `compute_charges` solve each connected component on its own. The
+    /// other entry points returning finished charges (`SolverContext`,
+    /// `InteractiveCharger`, the observed, hybridization and offset variants)
+    /// follow the same setting.
+    ///
+    /// Transfers are pairwise, so every fragment conserves its charge either way.
+    /// Splitting makes each fragment's result exactly what solving it alone would
+    /// give: with a `tolerance`, every fragment stops on its own convergence
+    /// instead of running until the slowest fragment converges. The reported
+    /// iteration count is then the largest over all fragments.
+    pub split_components: bool,
+    /// Hydrogen parameters keyed by the atomic number of the hydrogen's bonded
+    /// partner (its first bond), e.g. `parameters::polar_hydrogen_params()`.
+    /// Hydrogens whose partner has no entry use the generic set. Empty by default.
+    pub polar_hydrogen_params: HashMap<usize, GasteigerParams>,
+    /// Shift the final charges uniformly so they sum to exactly zero.
+    ///
+    /// This discards the net charge carried by the formal charges (an ammonium
+    /// ion comes out neutral), for tools that assume neutral molecules.
+    pub neutralize_output: bool,
+    /// Maps atomic-number codes to the element whose parameters they use, e.g. a
+    /// toolkit-specific deuterium or tritium code to 1. Charges are
+    /// isotope-independent, so isotopes should map to their base element.
+    /// Empty by default.
+    pub element_aliases: HashMap<usize, usize>,
+    pub degree_normalization: DegreeNormalization,
+    /// Per-element `(min, max)` charge bounds, keyed by atomic number, enforced
+    /// after every iteration of the iterative schedule, including Anderson
+    /// mixing. Charge clipped from an
+    /// atom is handed back in equal shares to its bonded, parameterized, unfrozen
+    /// neighbors, so the total charge is conserved; an atom without such
+    /// neighbors is left unclamped (`validate` reports the case where they are
+    /// all frozen), and frozen atoms are never clamped. Bounds are ignored by
+    /// `SolveMode::Linearized`, and `validate` rejects bounded atoms in that
+    /// mode. Empty (unbounded) by default.
+    pub charge_bounds: HashMap<usize, (f64, f64)>,
+    /// Per-element bond-order rules replacing the built-in ones, keyed by atomic
+    /// number. Each rule is `(min_order, hybridization)`; the first rule whose
+    /// `min_order` is at most the atom's highest bond order wins, so list them
+    /// from highest to lowest. Atoms matching no rule use the neighbor-count
+    /// rules. An entry also turns off the cumulated-double-bond rule and the
+    /// hypervalent-sulfur exception for that element. Empty by default; see
+    /// `order_hybridization_rules` for the rules in effect.
+    pub order_hybridizations: HashMap<usize, Vec<(f32, Hybridization)>>,
+    /// Sign and scale applied to the final charges, after neutralization and
+    /// before rounding. It never affects the iteration itself, only what is
+    /// returned. The identity by default.
+    pub output_convention: OutputConvention,
+    /// Seed for the only scheduling choice the solver makes:
+    /// `compute_charges_parallel` shuffles components with it before dealing them
+    /// to worker threads. Each component is solved independently and written
+    /// back by index, so charges are bit-identical for any seed, thread count or
+    /// `None`; the seed makes the schedule itself reproducible. `None` deals
+    /// components in index order.
+    pub seed: Option<u64>,
 }
 
-impl Default for GasteigerSolver {
-    fn default() -> Self {
-        Self {
-            iterations: 6,
-            damping: 0.5,
+impl Default for GasteigerSolver {
+    fn default() -> Self {
+        Self::with_model(QuadraticModel)
+    }
+}
+
+impl<M: ElectronegativityModel> GasteigerSolver<M> {
+    /// Creates a solver with the default schedule and a custom electronegativity model.
+    pub fn with_model(model: M) -> Self {
+        Self {
+            iterations: DEFAULT_ITERATIONS,
+            damping: DEFAULT_DAMPING,
+            model,
+            damping_mode: DampingMode::Geometric,
+            tolerance: None,
+            convergence_norm: ConvergenceNorm::LInfinity,
+            hybridization_thresholds: HybridizationThresholds::default(),
+            blend_fractional_orders: false,
+            unknown_elements: UnknownElementPolicy::Spectator,
+            element_hybridizations: HashMap::new(),
+            mode: SolveMode::Iterative,
+            output_decimals: None,
+            param_provider: Arc::new(BuiltinParams),
+            split_components: false,
+            polar_hydrogen_params: HashMap::new(),
+            neutralize_output: false,
+            element_aliases: HashMap::new(),
+            degree_normalization: DegreeNormalization::None,
+            charge_bounds: HashMap::new(),
+            order_hybridizations: HashMap::new(),
+            output_convention: OutputConvention::default(),
+            seed: None,
+        }
+    }
+
+    pub fn compute_charges<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.compute_charges_with_report(atoms, bonds).charges
+    }
+
+    /// Returns the hybridization perceived for every atom.
+    pub fn perceive_hybridizations<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<Hybridization>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let orders = bond_orders_by_atom(atoms, bonds);
+        atoms
+            .iter()
+            .zip(orders.iter())
+            .map(|(a, o)| self.hybridization_of(a, o))
+            .collect()
+    }
+
+    /// Whether every atom resolves to some parameter set after perception and fallbacks.
+    pub fn is_fully_parameterized<A, B>(&self, atoms: &[A], bonds: &[B]) -> bool
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.resolve_params(atoms, bonds).iter().all(Option::is_some)
+    }
+
+    /// Lists atoms whose guessed hybridization has no direct parameter entry,
+    /// together with that hybridization.
+    ///
+    /// These are the atoms that `compute_charges` would resolve through a
+    /// fallback (or leave as spectators), so the list tells which entries to add
+    /// to the parameter provider or which atoms to override via
+    /// `element_hybridizations`. Virtual sites are skipped.
+    pub fn check_parameter_coverage<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<(usize, Hybridization)>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let orders = bond_orders_by_atom(atoms, bonds);
+        atoms
+            .iter()
+            .enumerate()
+            .filter(|(_, atom)| !atom.is_virtual_site())
+            .filter_map(|(idx, atom)| {
+                let z = self.element_of(atom);
+                let guessed = self.hybridization_of(atom, &orders[idx]);
+                self.param_provider.params(z, guessed).is_none().then_some((idx, guessed))
+            })
+            .collect()
+    }
+
+    /// The hybridization and parameters `compute_charges` will use for atom
+    /// `atom_idx`, after perception, overrides and the fallback chain.
+    ///
+    /// The hybridization is the one whose entry was found (e.g. `Sp3` for an
+    /// element lacking the perceived state), or the perceived one when the
+    /// parameters come from elsewhere: `polar_hydrogen_params`, fractional-order
+    /// blending or `UnknownElementPolicy::GenericFallback`. Returns `None` for
+    /// spectators, virtual sites and out-of-range indices.
+    pub fn resolved_params_for<A, B>(&self, atom_idx: usize, atoms: &[A], bonds: &[B]) -> Option<(Hybridization, GasteigerParams)>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let atom = atoms.get(atom_idx)?;
+        let params = self.resolve_subset(atoms, bonds, &[atom_idx])[0]?;
+        let orders = bond_orders_by_atom(atoms, bonds);
+        let guessed = self.hybridization_of(atom, &orders[atom_idx]);
+        let used = match self.resolve_atom(self.element_of(atom), guessed) {
+            Some((h, p)) if p == params => h,
+            _ => guessed,
+        };
+        Some((used, params))
+    }
+
+    /// Lists atoms whose parameters are only an approximation of their perceived state.
+    ///
+    /// An atom is reported when its element has no parameters at all (it is
+    /// treated as a spectator), when the guessed hybridization has no entry and
+    /// a fallback was used, or when a sulfur or phosphorus atom is hypervalent
+    /// and therefore described with Sp3 parameters.
+    pub fn perception_warnings<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<(usize, String)>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let mut warnings = Vec::new();
+        let orders = bond_orders_by_atom(atoms, bonds);
+        for (idx, atom) in atoms.iter().enumerate() {
+            let z = self.element_of(atom);
+            let guessed = self.hybridization_of(atom, &orders[idx]);
+            match self.resolve_atom(z, guessed) {
+                None => warnings.push((idx, format!("no parameters for element {}; atom is a spectator", z))),
+                Some((used, _)) if used != guessed && guessed != Hybridization::Default => {
+                    warnings.push((idx, format!("no {:?} parameters for element {}; fell back to {:?}", guessed, z, used)));
+                }
+                Some(_) => {}
+            }
+
+            let neighbors = orders[idx].len();
+            let normal_valence = match z {
+                15 => 3,
+                16 => 2,
+                _ => continue,
+            };
+            if neighbors > normal_valence {
+                warnings.push((idx, format!("hypervalent element {} with {} neighbors; approximated with Sp3 parameters", z, neighbors)));
+            }
+        }
+        warnings
+    }
+
+    /// Computes charges paired with a per-atom confidence score in `[0, 1]`.
+    ///
+    /// Scoring rubric:
+    /// - atoms without parameters score `0.0` (their charge is just the formal charge);
+    /// - otherwise the score starts at `1.0`;
+    /// - each `perception_warnings` entry for the atom (fallback parameters,
+    ///   hypervalence) subtracts `0.25`;
+    /// - the fraction of bonded neighbors without parameters, times `0.5`, is
+    ///   subtracted, since those bonds transfer no charge;
+    /// - the result is clamped at `0.0`.
+    pub fn compute_charges_with_confidence<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<(f64, f64)>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let charges = self.compute_charges(atoms, bonds);
+        let params = self.resolve_params(atoms, bonds);
+        let n = atoms.len();
+
+        let mut score: Vec<f64> = params.iter().map(|p| if p.is_some() { 1.0 } else { 0.0 }).collect();
+        for (idx, _) in self.perception_warnings(atoms, bonds) {
+            score[idx] -= 0.25;
+        }
+        let mut neighbors = vec![0usize; n];
+        let mut unparameterized = vec![0usize; n];
+        for (i, j) in bonds.iter().map(|b| b.atom_indices()) {
+            if i >= n || j >= n || i == j || atoms[i].is_virtual_site() || atoms[j].is_virtual_site() {
+                continue;
+            }
+            for (a, b) in [(i, j), (j, i)] {
+                neighbors[a] += 1;
+                if params[b].is_none() {
+                    unparameterized[a] += 1;
+                }
+            }
+        }
+        for idx in 0..n {
+            if params[idx].is_none() {
+                score[idx] = 0.0;
+            } else if neighbors[idx] > 0 {
+                score[idx] -= 0.5 * unparameterized[idx] as f64 / neighbors[idx] as f64;
+            }
+        }
+        charges.into_iter().zip(score.into_iter().map(|s| s.max(0.0))).collect()
+    }
+
+    /// Checks the input for problems that `compute_charges` would silently tolerate.
+    ///
+    /// Every bond must reference existing atoms, and every formal charge must be
+    /// finite with `|q| <= MAX_FORMAL_CHARGE`; non-integer values and totals
+    /// (radicals) are accepted. Rings must be labelled either fully
+    /// aromatic or fully Kekulé. The bond orders around H, C, N and O may sum to
+    /// at most 1, 4, 4 and 3 (ammonium and oxonium are allowed), counting
+    /// aromatic bonds as in their Kekulé form so fused-ring bridgeheads and
+    /// ring carbonyls pass. Under `UnknownElementPolicy::Error`, every atom
+    /// must also have parameters. An unfrozen atom with `charge_bounds` whose
+    /// bonded, parameterized neighbors are all frozen is rejected, since charge
+    /// clipped from it would have nowhere to go, and so is any bounded atom under
+    /// `SolveMode::Linearized`, which does not enforce bounds.
+    pub fn validate<A, B>(&self, atoms: &[A], bonds: &[B]) -> Result<(), SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        for (index, atom) in atoms.iter().enumerate() {
+            let value = atom.formal_charge();
+            if !value.is_finite() || value.abs() > MAX_FORMAL_CHARGE {
+                return Err(SolverError::FormalChargeOutOfRange { index, value });
+            }
+        }
+        if self.unknown_elements == UnknownElementPolicy::Error {
+            let orders = bond_orders_by_atom(atoms, bonds);
+            for (index, atom) in atoms.iter().enumerate().filter(|(_, a)| !a.is_virtual_site()) {
+                let z = self.element_of(atom);
+                if self.resolve_atom(z, self.hybridization_of(atom, &orders[index])).is_none() {
+                    return Err(SolverError::UnknownElement { index, atomic_number: z });
+                }
+            }
+        }
+        for (bond_idx, bond) in bonds.iter().enumerate() {
+            let (i, j) = bond.atom_indices();
+            for index in [i, j] {
+                if index >= atoms.len() {
+                    return Err(SolverError::BondIndexOutOfRange { bond: bond_idx, index, n_atoms: atoms.len() });
+                }
+            }
+        }
+        let orders = bond_orders_by_atom(atoms, bonds);
+        for (index, atom) in atoms.iter().enumerate() {
+            let z = self.element_of(atom);
+            let Some(max) = max_valence(z) else { continue };
+            let total_order = valence_sum(&orders[index]);
+            if total_order > max + 1e-3 {
+                return Err(SolverError::Overvalence { index, z, total_order });
+            }
+        }
+        let labelled: Vec<(usize, usize, f32)> = bonds
+            .iter()
+            .map(|b| { let (i, j) = b.atom_indices(); (i, j, effective_order(b)) })
+            .collect();
+        if let Some(ring) = find_inconsistent_aromatic_ring(atoms.len(), &labelled) {
+            return Err(SolverError::InconsistentAromaticRing { atoms: ring });
+        }
+        if !self.charge_bounds.is_empty() {
+            let topology = self.prepare(atoms, bonds);
+            let absorbers = unfrozen_neighbors(&topology);
+            let has_partner = |i: usize| topology.pairs.iter().any(|&(a, b)| a == i || b == i);
+            for (index, absorbers) in absorbers.iter().enumerate() {
+                if topology.bounds[index].is_some() && self.mode == SolveMode::Linearized {
+                    return Err(SolverError::UnsupportedChargeBounds { index });
+                }
+                if topology.bounds[index].is_some() && !topology.frozen[index] && absorbers.is_empty() && has_partner(index) {
+                    return Err(SolverError::BoundedAtomWithoutAbsorber { index });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `compute_charges`, but runs `validate` first.
+    pub fn compute_charges_checked<A, B>(&self, atoms: &[A], bonds: &[B]) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.validate(atoms, bonds)?;
+        Ok(self.compute_charges(atoms, bonds))
+    }
+
+    /// Like `compute_charges`, reporting every atom's parameter resolution to `observer`.
+    pub fn compute_charges_observed<A, B, O>(&self, atoms: &[A], bonds: &[B], observer: &mut O) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+        O: ResolutionObserver + ?Sized,
+    {
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        let params = self.resolve_params_observed(atoms, bonds, observer);
+        let topology = self.topology_from_params(params, atoms, bonds);
+        self.equalize_molecule(&mut charges, &topology);
+        self.finish(atoms, bonds, &topology, &mut charges);
+        charges
+    }
+
+    /// Computes charges and reports how many iterations were needed.
+    pub fn compute_charges_with_report<A, B>(&self, atoms: &[A], bonds: &[B]) -> ChargeResult
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.report_with_total(atoms, bonds, None)
+    }
+
+    /// `compute_charges_with_report`, finishing with `finish_to_total`.
+    fn report_with_total<A, B>(&self, atoms: &[A], bonds: &[B], target_total: Option<f64>) -> ChargeResult
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        let periodic_bonds = bonds
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.is_periodic_image())
+            .map(|(k, _)| k)
+            .collect();
+        if bonds.is_empty() {
+            // Nothing can transfer; formal charges are already the answer.
+            let virtual_sites = self.finish_to_total(atoms, bonds, None, &mut charges, target_total);
+            return ChargeResult {
+                charges,
+                iterations: 0,
+                converged: self.tolerance.is_some(),
+                periodic_bonds,
+                orphaned_atoms: Vec::new(),
+                virtual_sites,
+            };
+        }
+        let topology = self.prepare(atoms, bonds);
+        let orphaned_atoms = orphaned_atoms(&topology, bonds);
+        let (iterations, converged) = self.equalize_molecule(&mut charges, &topology);
+        let virtual_sites = self.finish_to_total(atoms, bonds, Some(&topology), &mut charges, target_total);
+
+        ChargeResult { charges, iterations, converged, periodic_bonds, orphaned_atoms, virtual_sites }
+    }
+
+    /// Computes charges both as returned by `compute_charges` and averaged over
+    /// topologically equivalent atoms, returned as `(raw, symmetrized)`.
+    ///
+    /// Equivalent atoms are those sharing a label in
+    /// `molecule::equivalence_classes`. Averaging within a class conserves the
+    /// total charge; with `output_decimals` set, the symmetrized charges are
+    /// rounded again after averaging.
+    pub fn compute_charges_symmetrized<A, B>(&self, atoms: &[A], bonds: &[B]) -> (Vec<f64>, Vec<f64>)
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let raw = self.compute_charges(atoms, bonds);
+        let classes = equivalence_classes(atoms, bonds);
+        let n_classes = classes.iter().max().map_or(0, |m| m + 1);
+        let mut sums = vec![0.0; n_classes];
+        let mut counts = vec![0usize; n_classes];
+        for (&class, &q) in classes.iter().zip(&raw) {
+            sums[class] += q;
+            counts[class] += 1;
+        }
+        let mut symmetrized: Vec<f64> = classes.iter().map(|&class| sums[class] / counts[class] as f64).collect();
+        if let Some(decimals) = self.output_decimals {
+            round_charges(&mut symmetrized, decimals);
+        }
+        (raw, symmetrized)
+    }
+
+    /// Averages the charges of several resonance contributors of one molecule.
+    ///
+    /// Each contributor is a full description of the same atoms (in the same
+    /// order) with its own bond orders and formal charges, e.g. both Kekulé
+    /// forms of a carboxylate. Contributors are solved independently and their
+    /// charges averaged with equal weight.
+    pub fn compute_charges_resonance<A, B>(&self, contributors: &[(&[A], &[B])]) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let Some(&(first, _)) = contributors.first() else { return Ok(Vec::new()); };
+        let mut average = vec![0.0; first.len()];
+        for &(atoms, bonds) in contributors {
+            if atoms.len() != first.len() {
+                return Err(SolverError::AtomCountMismatch { expected: first.len(), found: atoms.len() });
+            }
+            for (avg, q) in average.iter_mut().zip(self.compute_charges(atoms, bonds)) {
+                *avg += q;
+            }
+        }
+        let n = contributors.len() as f64;
+        Ok(average.into_iter().map(|q| q / n).collect())
+    }
+
+    /// Averages the charges of several protonation states, each weighted by its
+    /// Boltzmann factor `exp(-E / kT)`.
+    ///
+    /// Every state is `(atoms, bonds, energy)` with the energy in kcal/mol
+    /// (relative energies suffice; only differences matter) and `temperature`
+    /// in kelvin. All states must list the same number of atoms in the same
+    /// order, so a proton absent from a state should be kept as a spectator,
+    /// e.g. an unbonded dummy atom. An empty `states` gives an empty vector.
+    /// Returns `SolverError::InvalidTemperature` unless `temperature` is a
+    /// finite positive number.
+    pub fn boltzmann_average_charges<A, B>(&self, states: &[(&[A], &[B], f64)], temperature: f64) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        if !(temperature.is_finite() && temperature > 0.0) {
+            return Err(SolverError::InvalidTemperature { value: temperature });
+        }
+        let Some(&(first, _, _)) = states.first() else { return Ok(Vec::new()); };
+        let kt = BOLTZMANN_KCAL * temperature;
+        // Shifting by the lowest energy keeps every exponent <= 0.
+        let lowest = states.iter().map(|s| s.2).fold(f64::INFINITY, f64::min);
+        let mut average = vec![0.0; first.len()];
+        let mut total_weight = 0.0;
+        for &(atoms, bonds, energy) in states {
+            if atoms.len() != first.len() {
+                return Err(SolverError::AtomCountMismatch { expected: first.len(), found: atoms.len() });
+            }
+            let weight = (-(energy - lowest) / kt).exp();
+            for (avg, q) in average.iter_mut().zip(self.compute_charges(atoms, bonds)) {
+                *avg += weight * q;
+            }
+            total_weight += weight;
+        }
+        Ok(average.into_iter().map(|q| q / total_weight).collect())
+    }
+
+    /// Solves several tautomeric forms for side-by-side comparison.
+    ///
+    /// Each form lists the same atoms in the same order; only bonds (and thus
+    /// where the mobile hydrogens sit) and formal charges differ. Results are
+    /// returned in the order of `forms`.
+    pub fn compare_tautomers<A, B>(&self, forms: &[(&[A], &[B])]) -> Vec<ChargeResult>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        forms
+            .iter()
+            .map(|&(atoms, bonds)| self.compute_charges_with_report(atoms, bonds))
+            .collect()
+    }
+
+    /// Per-atom charge change `q_b - q_a` between two forms of the same atoms.
+    ///
+    /// Both forms are solved independently and must list the same atoms in the
+    /// same order; only bonds and formal charges may differ. Gasteiger charges
+    /// depend on topology alone, so two conformers with identical bonds always
+    /// differ by zero. Returns `SolverError::AtomCountMismatch` if the atom
+    /// counts differ.
+    pub fn charge_difference<A, B>(&self, a: (&[A], &[B]), b: (&[A], &[B])) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        if a.0.len() != b.0.len() {
+            return Err(SolverError::AtomCountMismatch { expected: a.0.len(), found: b.0.len() });
+        }
+        let before = self.compute_charges(a.0, a.1);
+        let after = self.compute_charges(b.0, b.1);
+        Ok(after.iter().zip(&before).map(|(q_b, q_a)| q_b - q_a).collect())
+    }
+
+    /// Solves `molecules` in order while sharing one budget of
+    /// `max_total_iterations` between them.
+    ///
+    /// The budget is spent first come, first served: each molecule may run up to
+    /// `iterations` of whatever is left, and the iterations it reports are
+    /// deducted before the next one starts. Nothing is reserved for later
+    /// molecules, so a slow early molecule can starve the rest; put the ones
+    /// that matter most first. A molecule cut short keeps the charges reached so
+    /// far, and once the budget is exhausted the remaining molecules get their
+    /// formal charges (with the usual output processing) and `iterations: 0`;
+    /// either way they are reported with `converged: false` unless the
+    /// tolerance was met within the iterations they did get. Molecules without
+    /// bonds need no iterations and are unaffected. With `split_components` a
+    /// molecule is charged its reported count, the largest over its fragments.
+    /// `SolveMode::Linearized` Newton steps are counted but not capped.
+    pub fn compute_charges_batch<A, B>(&self, molecules: &[(&[A], &[B])], max_total_iterations: usize) -> Vec<ChargeResult>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+        M: Clone,
+    {
+        let mut limited = self.clone();
+        let mut remaining = max_total_iterations;
+        molecules
+            .iter()
+            .map(|&(atoms, bonds)| {
+                limited.iterations = self.iterations.min(remaining);
+                let result = limited.compute_charges_with_report(atoms, bonds);
+                remaining = remaining.saturating_sub(result.iterations);
+                result
+            })
+            .collect()
+    }
+
+    /// Returns snapshots of the charges every `stride` iterations, e.g. for animation.
+    ///
+    /// Frame 0 is the formal-charge seed. A frame is then taken after every
+    /// `stride`-th iteration, and the final state is always the last frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is zero.
+    pub fn compute_charges_frames<A, B>(&self, atoms: &[A], bonds: &[B], stride: usize) -> Vec<Vec<f64>>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        assert!(stride > 0, "stride must be positive");
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        let topology = self.prepare(atoms, bonds);
+        let mut frames = vec![charges.clone()];
+        let mut last_frame = 0;
+
+        let (iterations, _) = self.equalize_with(&mut charges, &topology, |k, q| {
+            if k % stride == 0 {
+                frames.push(q.to_vec());
+                last_frame = k;
+            }
+        });
+        if last_frame != iterations {
+            frames.push(charges);
+        }
+        frames
+    }
+
+    /// Per-atom geometric convergence rate estimated from the iteration history.
+    ///
+    /// The charges are recorded after every iteration (as by
+    /// `compute_charges_frames` with stride 1) and for each atom the last two
+    /// updates, `Δₖ = qₖ − qₖ₋₁`, are compared: the rate is `|Δₙ| / |Δₙ₋₁|`.
+    /// Values near `damping` are typical; larger values mark slowly converging
+    /// atoms. Atoms whose last update is zero report `0.0`; this includes atoms
+    /// held at a `charge_bounds` limit and their neighbors once clamping has
+    /// settled, so `0.0` does not by itself mean the free solve converged. An
+    /// atom whose last update is nonzero after a zero one (e.g. released from a
+    /// bound, or leaving an exact χ tie) has no defined rate and reports `NaN`,
+    /// as does every atom when there were fewer than two iterations.
+    pub fn convergence_rate<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let frames = self.compute_charges_frames(atoms, bonds, 1);
+        if frames.len() < 3 {
+            return vec![f64::NAN; atoms.len()];
+        }
+        let [before, previous, last] = [&frames[frames.len() - 3], &frames[frames.len() - 2], &frames[frames.len() - 1]];
+        (0..atoms.len())
+            .map(|i| {
+                let (older, newer) = ((previous[i] - before[i]).abs(), (last[i] - previous[i]).abs());
+                if newer == 0.0 {
+                    0.0
+                } else if older == 0.0 {
+                    f64::NAN
+                } else {
+                    newer / older
+                }
+            })
+            .collect()
+    }
+
+    /// Computes charges from parallel arrays (structure of arrays) instead of trait objects.
+    ///
+    /// Atom `k` is `(atomic_numbers[k], formal_charges[k])` and bond `k` is
+    /// `(bond_i[k], bond_j[k], bond_order[k])`. The arrays are copied into the
+    /// built-in `Atom`/`Bond` types once and solved as usual.
+    pub fn compute_charges_soa(
+        &self,
+        atomic_numbers: &[usize],
+        formal_charges: &[f32],
+        bond_i: &[usize],
+        bond_j: &[usize],
+        bond_order: &[f32],
+    ) -> Result<Vec<f64>, SolverError> {
+        if formal_charges.len() != atomic_numbers.len() {
+            return Err(SolverError::AtomCountMismatch { expected: atomic_numbers.len(), found: formal_charges.len() });
+        }
+        for found in [bond_j.len(), bond_order.len()] {
+            if found != bond_i.len() {
+                return Err(SolverError::BondCountMismatch { expected: bond_i.len(), found });
+            }
+        }
+        let atoms: Vec<Atom> = atomic_numbers.iter().zip(formal_charges).map(|(&z, &q)| Atom::new(z, q)).collect();
+        let bonds: Vec<Bond> = bond_i
+            .iter()
+            .zip(bond_j)
+            .zip(bond_order)
+            .map(|((&i, &j), &order)| Bond::new(i, j, order))
+            .collect();
+        Ok(self.compute_charges(&atoms, &bonds))
+    }
+
+    /// Net charge moved across each bond during the solve, indexed like `bonds`.
+    ///
+    /// Entry `k` is the total change in the partial charge of
+    /// `bonds[k].atom_indices().0` caused by that bond; the other atom changes by
+    /// the opposite amount. A negative entry means the first atom pulled electron
+    /// density from its partner. Bonds that
+    /// take no part in the solve report `0.0`. Transfers are only tracked by the
+    /// iterative schedule; under `SolveMode::Linearized` and
+    /// `DampingMode::Anderson` every entry is `NaN`.
+    pub fn bond_transfers<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        if self.mode == SolveMode::Linearized || matches!(self.damping_mode, DampingMode::Anderson { .. }) {
+            return vec![f64::NAN; bonds.len()];
+        }
+        let topology = self.prepare(atoms, bonds);
+        let n = atoms.len();
+        // `topology.pairs` keeps the bonds passing this filter, in order.
+        let solved: Vec<usize> = bonds
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| {
+                let (i, j) = b.atom_indices();
+                i < n && j < n && topology.params[i].is_some() && topology.params[j].is_some()
+            })
+            .map(|(k, _)| k)
+            .collect();
+
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        let mut pair_transfers = vec![0.0; topology.pairs.len()];
+        self.equalize_tracked(&mut charges, &topology, |_, _| {}, Some(&mut pair_transfers));
+
+        let mut transfers = vec![0.0; bonds.len()];
+        for (k, t) in solved.into_iter().zip(pair_transfers) {
+            transfers[k] = t;
+        }
+        transfers
+    }
+
+    /// Direction of net electron flow across each bond, indexed like `bonds`.
+    ///
+    /// Entry `k` is `Some((from, to))` with electron density moving from atom
+    /// `from` to atom `to` (which ends up more negative for it), derived from
+    /// `bond_transfers`. Bonds whose net transfer is below `FLOW_THRESHOLD`, bonds
+    /// outside the solve, and every bond under modes that do not track transfers
+    /// give `None`.
+    pub fn charge_flow<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<Option<(usize, usize)>>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        bonds
+            .iter()
+            .zip(self.bond_transfers(atoms, bonds))
+            .map(|(bond, t)| {
+                let (i, j) = bond.atom_indices();
+                if t.is_nan() || t.abs() < FLOW_THRESHOLD {
+                    None
+                } else if t < 0.0 {
+                    Some((j, i))
+                } else {
+                    Some((i, j))
+                }
+            })
+            .collect()
+    }
+
+    /// A human-readable report of how every atom got its charge.
+    ///
+    /// One block per atom lists its element (after `element_aliases`, noting the
+    /// original code), perceived hybridization, the parameters used, the final charge and χ, and for every bond how much
+    /// electron density the atom pulled from or gave to that partner. The format is meant for
+    /// reading, not parsing, and may change.
+    pub fn explain<A, B>(&self, atoms: &[A], bonds: &[B]) -> String
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        use std::fmt::Write;
+
+        let charges = self.compute_charges(atoms, bonds);
+        let hybridizations = self.perceive_hybridizations(atoms, bonds);
+        let params = self.prepare(atoms, bonds).params;
+        let transfers = self.bond_transfers(atoms, bonds);
+
+        let mut out = String::new();
+        for (idx, atom) in atoms.iter().enumerate() {
+            let z = self.element_of(atom);
+            let _ = write!(out, "atom {} (Z={}", idx, z);
+            if atom.atomic_number() != z {
+                let _ = write!(out, " via alias {}", atom.atomic_number());
+            }
+            let _ = write!(out, ", {:?}): q = {:+.4}", hybridizations[idx], charges[idx]);
+            match &params[idx] {
+                Some(p) => {
+                    let chi = self.calculate_electronegativity(p, charges[idx]);
+                    let _ = writeln!(out, ", chi = {:.4}, params a={} b={} c={}", chi, p.a, p.b, p.c);
+                }
+                None => {
+                    let _ = writeln!(out, ", no parameters (spectator)");
+                }
+            }
+            for (k, bond) in bonds.iter().enumerate() {
+                let (i, j) = bond.atom_indices();
+                let (partner, dq) = match (i == idx, j == idx) {
+                    (true, false) => (j, transfers[k]),
+                    (false, true) => (i, -transfers[k]),
+                    _ => continue,
+                };
+                let partner_z = atoms.get(partner).map_or(0, |a| self.element_of(a));
+                let action = if dq <= 0.0 { "pulled charge from" } else { "gave charge to" };
+                let _ = writeln!(out, "  bond {}: {} atom {} (Z={}), dq = {:+.4}", k, action, partner, partner_z, dq);
+            }
+        }
+        out
+    }
+
+    /// Computes charges for a system specified by its net charge, as in a
+    /// quantum-chemistry input ("charge 1").
+    ///
+    /// With `site = Some(i)`, the difference between `net_charge` and the sum of
+    /// the formal charges is added to atom `i`'s formal charge (e.g. the
+    /// protonated nitrogen of an amine) before solving. With `site = None`, the
+    /// formal charges must already sum to `net_charge` (within 1e-6).
+    pub fn compute_charges_with_net_charge<A, B>(
+        &self,
+        atoms: &[A],
+        bonds: &[B],
+        net_charge: i32,
+        site: Option<usize>,
+    ) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let expected = net_charge as f64;
+        let found: f64 = atoms.iter().map(|a| a.formal_charge() as f64).sum();
+        let Some(site) = site else {
+            if (found - expected).abs() > 1e-6 {
+                return Err(SolverError::NetChargeMismatch { expected, found });
+            }
+            return Ok(self.compute_charges(atoms, bonds));
+        };
+        if site >= atoms.len() {
+            return Err(SolverError::AtomIndexOutOfRange { index: site, n_atoms: atoms.len() });
+        }
+        let seeded: Vec<ExtraCharge<'_, A>> = atoms
+            .iter()
+            .enumerate()
+            .map(|(i, atom)| ExtraCharge { atom, extra: if i == site { (expected - found) as f32 } else { 0.0 } })
+            .collect();
+        Ok(self.compute_charges(&seeded, bonds))
+    }
+
+    /// Computes charges and keeps only the atoms of element `z`, as
+    /// `(index, charge)` pairs in index order. Elements are compared after
+    /// `element_aliases`, so e.g. deuterium mapped to 1 counts as hydrogen.
+    pub fn charges_for_element<A, B>(&self, atoms: &[A], bonds: &[B], z: usize) -> Vec<(usize, f64)>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.compute_charges(atoms, bonds)
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| self.element_of(&atoms[i]) == z)
+            .collect()
+    }
+
+    /// Solves connected components on up to `threads` scoped worker threads.
+    ///
+    /// Perception and parameters are resolved for the whole molecule first, as
+    /// in `compute_charges`; the components are then solved independently, so
+    /// the result equals `compute_charges` with `split_components` set. See
+    /// `seed` for how components are distributed.
+    pub fn compute_charges_parallel<A, B>(&self, atoms: &[A], bonds: &[B], threads: usize) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+        M: Sync,
+    {
+        let topology = self.prepare(atoms, bonds);
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        let mut parts = split_topology(&topology);
+        if let Some(seed) = self.seed {
+            shuffle_seeded(&mut parts, seed);
+        }
+        let threads = threads.clamp(1, parts.len().max(1));
+
+        let seeds = &charges;
+        let solved: Vec<Vec<(usize, f64)>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|t| {
+                    let mine: Vec<&(Vec<usize>, Topology)> = parts.iter().skip(t).step_by(threads).collect();
+                    scope.spawn(move || {
+                        let mut out = Vec::new();
+                        for (indices, sub) in mine {
+                            let mut sub_charges: Vec<f64> = indices.iter().map(|&a| seeds[a]).collect();
+                            self.equalize(&mut sub_charges, sub);
+                            out.extend(indices.iter().copied().zip(sub_charges));
+                        }
+                        out
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().expect("charge worker panicked")).collect()
+        });
+        for (atom, q) in solved.into_iter().flatten() {
+            charges[atom] = q;
+        }
+        self.finish(atoms, bonds, &topology, &mut charges);
+        charges
+    }
+
+    /// Lazily solves one connected component at a time, yielding each
+    /// component's atom indices (ascending) and their charges.
+    ///
+    /// Components come in order of their lowest atom index, isolated atoms
+    /// included. Only the grouping of indices is computed up front; each item's
+    /// charges are solved when it is pulled, exactly as if that fragment were
+    /// passed to `compute_charges` on its own. Bonds with an out-of-range
+    /// endpoint are ignored.
+    pub fn iter_component_charges<'a, A, B>(&'a self, atoms: &'a [A], bonds: &'a [B]) -> impl Iterator<Item = (Vec<usize>, Vec<f64>)> + 'a
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let n = atoms.len();
+        let labels = component_labels(n, bonds.iter().map(|b| b.atom_indices()));
+        let mut slot = vec![usize::MAX; n];
+        let mut local = vec![0; n];
+        let mut members: Vec<Vec<usize>> = Vec::new();
+        for (atom, &root) in labels.iter().enumerate() {
+            if slot[root] == usize::MAX {
+                slot[root] = members.len();
+                members.push(Vec::new());
+            }
+            local[atom] = members[slot[root]].len();
+            members[slot[root]].push(atom);
+        }
+        let mut component_bonds: Vec<Vec<usize>> = vec![Vec::new(); members.len()];
+        for (k, (i, j)) in bonds.iter().map(|b| b.atom_indices()).enumerate() {
+            if i < n && j < n {
+                component_bonds[slot[labels[i]]].push(k);
+            }
+        }
+
+        members.into_iter().zip(component_bonds).map(move |(indices, bond_ids)| {
+            let sub_atoms: Vec<&A> = indices.iter().map(|&i| &atoms[i]).collect();
+            let sub_bonds: Vec<LocalBond<'_, B>> = bond_ids
+                .iter()
+                .map(|&k| {
+                    let (i, j) = bonds[k].atom_indices();
+                    LocalBond { bond: &bonds[k], pair: (local[i], local[j]) }
+                })
+                .collect();
+            let charges = self.compute_charges(&sub_atoms, &sub_bonds);
+            (indices, charges)
+        })
+    }
+
+    /// Computes charges keyed by each atom's `id()` instead of its position.
+    ///
+    /// Bond indices still refer to positions in `atoms`. If two atoms share an
+    /// id, the later one wins.
+    pub fn compute_charges_by_id<A, B>(&self, atoms: &[A], bonds: &[B]) -> HashMap<u64, f64>
+    where
+        A: IdentifiedAtom,
+        B: GasteigerBond,
+    {
+        let charges = self.compute_charges(atoms, bonds);
+        atoms.iter().map(|a| a.id()).zip(charges).collect()
+    }
+
+    /// Computes charges and then imposes a net charge of `target_total`.
+    ///
+    /// After the usual equalization, the difference between `target_total` and the
+    /// formal-charge sum is spread uniformly over all atoms, so relative charge
+    /// differences are unchanged. `target_total` is in elementary charges and
+    /// replaces `neutralize_output`; the shift happens before `output_convention`
+    /// and `output_decimals` are applied, so the returned charges sum to
+    /// `sign · scale · target_total` up to rounding. Returns an empty vector for
+    /// an empty molecule.
+    pub fn compute_charges_with_total<A, B>(&self, atoms: &[A], bonds: &[B], target_total: f64) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.report_with_total(atoms, bonds, Some(target_total)).charges
+    }
+
+    /// Estimates σ-holes on heavy halogens (Br, I) from 3D coordinates.
+    ///
+    /// Charges are solved as usual; for each halogen with exactly one bond, the
+    /// axial charge is the isotropic charge plus `sigma_hole_bias`. Halogens whose
+    /// bond has zero length or whose coordinates are missing are skipped.
+    /// `compute_charges` itself is unaffected.
+    pub fn sigma_holes<A, B>(&self, atoms: &[A], bonds: &[B], coords: &[[f64; 3]]) -> Vec<SigmaHole>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let charges = self.compute_charges(atoms, bonds);
+        let mut holes = Vec::new();
+
+        for (idx, atom) in atoms.iter().enumerate() {
+            let Some(bias) = sigma_hole_bias(self.element_of(atom)) else { continue; };
+            let partners: Vec<usize> = bonds
+                .iter()
+                .filter_map(|b| match b.atom_indices() {
+                    (i, j) if i == idx => Some(j),
+                    (i, j) if j == idx => Some(i),
+                    _ => None,
+                })
+                .collect();
+            if partners.len() != 1 || idx >= coords.len() || partners[0] >= coords.len() {
+                continue;
+            }
+
+            let (x, r) = (coords[idx], coords[partners[0]]);
+            let axis = [x[0] - r[0], x[1] - r[1], x[2] - r[2]];
+            let length = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+            if length < 1e-12 {
+                continue;
+            }
+
+            holes.push(SigmaHole {
+                atom: idx,
+                isotropic_charge: charges[idx],
+                axial_charge: charges[idx] + bias,
+                direction: [axis[0] / length, axis[1] / length, axis[2] / length],
+            });
         }
+
+        holes
     }
-}
 
-impl GasteigerSolver {
-    pub fn compute_charges<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<f64>
+    /// Computes charges as fixed-point integers in milli-electrons.
+    ///
+    /// Each charge is multiplied by 1000 and rounded to the nearest integer,
+    /// with ties rounded away from zero (`f64::round`). The result is
+    /// platform-independent and safe to hash or compare exactly.
+    pub fn compute_charges_fixed<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<i32>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.compute_charges(atoms, bonds)
+            .iter()
+            .map(|&q| (q * 1000.0).round() as i32)
+            .collect()
+    }
+
+    /// Sensitivity of every partial charge to the formal charge of atom `wrt`, ∂q_i/∂Q_wrt.
+    ///
+    /// Computed by central finite differences: the formal charge of `wrt` is
+    /// perturbed by ±`SENSITIVITY_STEP`, the system is re-solved with the same
+    /// perceived parameters, and the two results are differenced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `wrt` is not a valid atom index.
+    pub fn charge_sensitivity<A, B>(&self, atoms: &[A], bonds: &[B], wrt: usize) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        assert!(wrt < atoms.len(), "atom index {} out of range", wrt);
+        let topology = self.prepare(atoms, bonds);
+        let solve = |step: f64| {
+            let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+            charges[wrt] += step;
+            self.equalize_molecule(&mut charges, &topology);
+            charges
+        };
+
+        let plus = solve(SENSITIVITY_STEP);
+        let minus = solve(-SENSITIVITY_STEP);
+        plus.iter()
+            .zip(minus.iter())
+            .map(|(p, m)| (p - m) / (2.0 * SENSITIVITY_STEP))
+            .collect()
+    }
+
+    /// Computes charges using caller-supplied hybridizations instead of perceiving them.
+    ///
+    /// `hybridizations[i]` is looked up for atom `i` with the usual fallback chain
+    /// and unknown-element policy; fractional-order blending is not applied.
+    pub fn compute_charges_with_hybridizations<A, B>(
+        &self,
+        atoms: &[A],
+        bonds: &[B],
+        hybridizations: &[Hybridization],
+    ) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        if hybridizations.len() != atoms.len() {
+            return Err(SolverError::AtomCountMismatch { expected: atoms.len(), found: hybridizations.len() });
+        }
+        let params = atoms
+            .iter()
+            .zip(hybridizations)
+            .map(|(a, &h)| if a.is_virtual_site() { None } else { self.lookup(self.element_of(a), h) })
+            .collect();
+        let topology = self.topology_from_params(params, atoms, bonds);
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        self.equalize_molecule(&mut charges, &topology);
+        self.finish(atoms, bonds, &topology, &mut charges);
+        Ok(charges)
+    }
+
+    /// Computes charges polarized by fixed external point charges, as in QM/MM
+    /// embedding.
+    ///
+    /// `external` lists `(charge, position)` pairs in e and Å, and `coords` gives
+    /// the molecule's atom positions. The external potential `Vᵢ` at each atom
+    /// (in V, see `electrostatics::electrostatic_potential_at`) is added to that
+    /// atom's electronegativity, `χᵢ(q) + Vᵢ`: a positive potential stabilizes
+    /// electron density on the atom, so it draws charge and becomes more
+    /// negative. Since χ is in eV per unit charge, no further scaling is applied.
+    /// The coupling is one-way and constant: the molecule's own charges do not
+    /// enter `Vᵢ`, and `χ⁺` denominators are unchanged. Only the iterative and
+    /// linearized equalization see the potential (not the `simd` paths).
+    pub fn compute_charges_in_field<A, B>(
+        &self,
+        atoms: &[A],
+        bonds: &[B],
+        external: &[(f64, [f64; 3])],
+        coords: &[[f64; 3]],
+    ) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        if coords.len() != atoms.len() {
+            return Err(SolverError::AtomCountMismatch { expected: atoms.len(), found: coords.len() });
+        }
+        let (charges, positions): (Vec<f64>, Vec<[f64; 3]>) = external.iter().copied().unzip();
+        let offsets = coords
+            .iter()
+            .map(|&r| electrostatic_potential_at(&charges, &positions, r))
+            .collect::<Result<Vec<f64>, _>>()?;
+        Ok(self.compute_charges_with_offsets(atoms, bonds, offsets))
+    }
+
+    /// Computes charges with `chi_offset[i]` (eV) added to atom `i`'s
+    /// electronegativity at every evaluation, e.g. per-atom-type corrections fitted
+    /// against QM charges.
+    ///
+    /// All-zero offsets reproduce `compute_charges`. Nonzero offsets make an atom's
+    /// χ depend on more than its element and hybridization, so two atoms sharing
+    /// parameters can end up with different charges in otherwise symmetric
+    /// environments. The `χ⁺` denominators are not offset.
+    pub fn compute_charges_with_chi_offset<A, B>(&self, atoms: &[A], bonds: &[B], chi_offset: &[f64]) -> Result<Vec<f64>, SolverError>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        if chi_offset.len() != atoms.len() {
+            return Err(SolverError::AtomCountMismatch { expected: atoms.len(), found: chi_offset.len() });
+        }
+        Ok(self.compute_charges_with_offsets(atoms, bonds, chi_offset.to_vec()))
+    }
+
+    /// Solves with `offsets` added to every atom's χ; `offsets.len()` must match `atoms`.
+    fn compute_charges_with_offsets<A, B>(&self, atoms: &[A], bonds: &[B], offsets: Vec<f64>) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let mut topology = self.prepare(atoms, bonds);
+        topology.chi_offsets = offsets;
+        let mut charges: Vec<f64> = atoms.iter().map(|a| a.formal_charge() as f64).collect();
+        self.equalize_molecule(&mut charges, &topology);
+        self.finish(atoms, bonds, &topology, &mut charges);
+        charges
+    }
+
+    /// Electronegativity of every atom at zero charge, without iterating.
+    ///
+    /// Uses the same perception and parameter fallbacks as `compute_charges`; for
+    /// the quadratic model this is just `a`. Atoms without parameters get `NaN`.
+    pub fn initial_electronegativities<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<f64>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.resolve_params(atoms, bonds)
+            .iter()
+            .map(|p| p.as_ref().map_or(f64::NAN, |p| self.calculate_electronegativity(p, 0.0)))
+            .collect()
+    }
+
+    /// Global damping factor applied at each of the `iterations` iterations:
+    /// `[1.0, damping, damping², …]`.
+    ///
+    /// Under `DampingMode::Adaptive` individual bonds are additionally scaled by
+    /// per-atom factors that depend on the molecule. `SolveMode::Linearized` and
+    /// `DampingMode::Anderson` use no damping schedule and return an empty vector.
+    pub fn damping_sequence(&self) -> Vec<f64> {
+        if self.mode == SolveMode::Linearized || matches!(self.damping_mode, DampingMode::Anderson { .. }) {
+            return Vec::new();
+        }
+        std::iter::successors(Some(1.0), |d| Some(d * self.damping))
+            .take(self.iterations)
+            .collect()
+    }
+
+    pub(crate) fn resolve_params<A, B>(&self, atoms: &[A], bonds: &[B]) -> Vec<Option<GasteigerParams>>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.resolve_params_observed(atoms, bonds, &mut ())
+    }
+
+    fn resolve_params_observed<A, B, O>(&self, atoms: &[A], bonds: &[B], observer: &mut O) -> Vec<Option<GasteigerParams>>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+        O: ResolutionObserver + ?Sized,
+    {
+        self.resolve_indexed(atoms, bonds, 0..atoms.len(), observer)
+    }
+
+    /// Resolves parameters for the atoms at `indices` only, in that order.
+    pub(crate) fn resolve_subset<A, B>(&self, atoms: &[A], bonds: &[B], indices: &[usize]) -> Vec<Option<GasteigerParams>>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.resolve_indexed(atoms, bonds, indices.iter().copied(), &mut ())
+    }
+
+    fn resolve_indexed<A, B, I, O>(&self, atoms: &[A], bonds: &[B], indices: I, observer: &mut O) -> Vec<Option<GasteigerParams>>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+        I: IntoIterator<Item = usize>,
+        O: ResolutionObserver + ?Sized,
+    {
+        // Many atoms share an (element, hybridization) context, so the fallback
+        // chain is resolved once per context.
+        let orders = bond_orders_by_atom(atoms, bonds);
+        let hydrogen_partners = self.hydrogen_partners(atoms, bonds);
+        let mut memo: HashMap<(usize, Hybridization), Option<GasteigerParams>> = HashMap::new();
+        indices
+            .into_iter()
+            .map(|index| {
+                let (atom, o) = (&atoms[index], &orders[index]);
+                let z = self.element_of(atom);
+                let hybrid = self.hybridization_of(atom, o);
+                let polar_hydrogen = hydrogen_partners
+                    .get(&index)
+                    .and_then(|partner| self.polar_hydrogen_params.get(partner));
+                let params = if atom.is_virtual_site() {
+                    None
+                } else if let Some(&p) = polar_hydrogen {
+                    Some(p)
+                } else {
+                    let blended = self.declared_hybridization(atom).is_none().then(|| self.blended_params(z, o)).flatten();
+                    blended.or_else(|| {
+                        *memo.entry((z, hybrid)).or_insert_with(|| self.lookup(z, hybrid))
+                    })
+                };
+                observer.on_atom_resolved(index, z, hybrid, params.as_ref());
+                params
+            })
+            .collect()
+    }
+
+    /// Maps each hydrogen index to the atomic number of its first bonded partner.
+    /// Empty when no polar hydrogen parameters are configured.
+    fn hydrogen_partners<A, B>(&self, atoms: &[A], bonds: &[B]) -> HashMap<usize, usize>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let mut partners = HashMap::new();
+        if self.polar_hydrogen_params.is_empty() {
+            return partners;
+        }
+        for (i, j) in bonds.iter().map(|b| b.atom_indices()) {
+            if i >= atoms.len() || j >= atoms.len() {
+                continue;
+            }
+            for (h, other) in [(i, j), (j, i)] {
+                if self.element_of(&atoms[h]) == 1 {
+                    partners.entry(h).or_insert(self.element_of(&atoms[other]));
+                }
+            }
+        }
+        partners
+    }
+
+    /// Sp3/Sp2 blend for an atom with a fractional highest bond order, when enabled.
+    fn blended_params(&self, atomic_number: usize, orders: &[BondOrder]) -> Option<GasteigerParams> {
+        if !self.blend_fractional_orders {
+            return None;
+        }
+        let max_order = orders.iter().map(|o| o.as_f32()).fold(0.0f32, f32::max) as f64;
+        if max_order <= 1.0 || max_order >= 2.0 {
+            return None;
+        }
+        let sp3 = self.param_provider.params(atomic_number, Hybridization::Sp3)?;
+        let sp2 = self.param_provider.params(atomic_number, Hybridization::Sp2)?;
+        Some(sp3.lerp(&sp2, max_order - 1.0))
+    }
+
+    /// The element used for perception and parameters, after `element_aliases`.
+    fn element_of<A: GasteigerAtom>(&self, atom: &A) -> usize {
+        let z = atom.atomic_number();
+        self.element_aliases.get(&z).copied().unwrap_or(z)
+    }
+
+    /// `resolve_atom` followed by the unknown-element policy.
+    fn lookup(&self, atomic_number: usize, hybridization: Hybridization) -> Option<GasteigerParams> {
+        self.resolve_atom(atomic_number, hybridization).map(|(_, p)| p).or(match self.unknown_elements {
+            UnknownElementPolicy::GenericFallback(p) => Some(p),
+            _ => None,
+        })
+    }
+
+    /// Parameter lookup with the fallback chain guessed → Sp3 → Default.
+    /// Returns the hybridization whose entry was actually used.
+    fn resolve_atom(&self, atomic_number: usize, guessed: Hybridization) -> Option<(Hybridization, GasteigerParams)> {
+        [guessed, Hybridization::Sp3, Hybridization::Default]
+            .into_iter()
+            .find_map(|h| self.param_provider.params(atomic_number, h).map(|p| (h, p)))
+    }
+
+    /// Resolves everything about a molecule that does not depend on its charges.
+    pub(crate) fn prepare<A, B>(&self, atoms: &[A], bonds: &[B]) -> Topology
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.topology_from_params(self.resolve_params(atoms, bonds), atoms, bonds)
+    }
+
+    pub(crate) fn topology_from_params<A, B>(&self, params: Vec<Option<GasteigerParams>>, atoms: &[A], bonds: &[B]) -> Topology
     where
         A: GasteigerAtom,
         B: GasteigerBond,
     {
         let n_atoms = atoms.len();
-        let mut charges = vec![0.0; n_atoms];
-        
-        for (i, atom) in atoms.iter().enumerate() {
-            charges[i] = atom.formal_charge() as f64;
+        let chi_plus = params
+            .iter()
+            .map(|p| p.as_ref().map_or(0.0, |p| self.calculate_electronegativity(p, 1.0)))
+            .collect();
+        let pairs = bonds
+            .iter()
+            .map(|b| b.atom_indices())
+            .filter(|&(i, j)| i < n_atoms && j < n_atoms)
+            .filter(|&(i, j)| params[i].is_some() && params[j].is_some())
+            .collect();
+        let bounds = atoms.iter().map(|a| self.charge_bounds.get(&self.element_of(a)).copied()).collect();
+        let frozen = atoms.iter().map(|a| a.is_frozen()).collect();
+        let chi_offsets = vec![0.0; n_atoms];
+        Topology { params, chi_plus, pairs, bounds, frozen, chi_offsets }
+    }
+
+    /// Post-processing applied to every final charge vector: terminal-oxygen
+    /// symmetrization, virtual-site relocation, neutralization, the output
+    /// convention, then output rounding. Returns the virtual-site indices.
+    pub(crate) fn finish<A, B>(&self, atoms: &[A], bonds: &[B], topology: &Topology, charges: &mut [f64]) -> Vec<usize>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        self.finish_to_total(atoms, bonds, Some(topology), charges, None)
+    }
+
+    /// `finish`, shifting the charges uniformly to sum to `target_total` (in e)
+    /// instead of applying `neutralize_output`, when one is given.
+    /// Without a `topology` (nothing was solved) no symmetrization is needed.
+    fn finish_to_total<A, B>(
+        &self,
+        atoms: &[A],
+        bonds: &[B],
+        topology: Option<&Topology>,
+        charges: &mut [f64],
+        target_total: Option<f64>,
+    ) -> Vec<usize>
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        if let Some(topology) = topology {
+            self.symmetrize_terminal_oxygens(atoms, bonds, topology, charges);
+        }
+        let virtual_sites = relocate_to_virtual_sites(atoms, bonds, charges);
+        let target_total = target_total.or(self.neutralize_output.then_some(0.0));
+        if let Some(total) = target_total.filter(|_| !charges.is_empty()) {
+            let shift = (total - charges.iter().sum::<f64>()) / charges.len() as f64;
+            for q in charges.iter_mut() {
+                *q += shift;
+            }
+        }
+        let factor = self.output_convention.sign * self.output_convention.scale;
+        if factor != 1.0 {
+            for q in charges.iter_mut() {
+                *q *= factor;
+            }
+        }
+        if let Some(decimals) = self.output_decimals {
+            round_charges(charges, decimals);
+        }
+        virtual_sites
+    }
+
+    /// Averages the charges of equivalent terminal oxygens.
+    ///
+    /// Two oxygens are equivalent when each has a single bond, both bonds go to
+    /// the same atom with the same effective order, their formal charges match,
+    /// and the solve saw them identically: same resolved parameters, same χ
+    /// offset, neither frozen. That covers nitro groups and carboxylates drawn
+    /// with 1.5 orders, while an oxygen polarized by an offset or field, given
+    /// its own hybridization, or frozen keeps its own charge. The iteration
+    /// treats equivalent oxygens identically, but the order in which transfers
+    /// are summed can still leave them a few ulps apart.
+    fn symmetrize_terminal_oxygens<A, B>(&self, atoms: &[A], bonds: &[B], topology: &Topology, charges: &mut [f64])
+    where
+        A: GasteigerAtom,
+        B: GasteigerBond,
+    {
+        let n = atoms.len();
+        let mut degree = vec![0usize; n];
+        for (i, j) in bonds.iter().map(|b| b.atom_indices()) {
+            if i < n && j < n {
+                degree[i] += 1;
+                degree[j] += 1;
+            }
+        }
+        type Key = (usize, i64, u32, Option<[u64; 3]>, u64);
+        let mut groups: HashMap<Key, Vec<usize>> = HashMap::new();
+        for bond in bonds {
+            let (i, j) = bond.atom_indices();
+            if i >= n || j >= n {
+                continue;
+            }
+            let order = (effective_order(bond) * 4.0).round() as i64;
+            for (oxygen, partner) in [(i, j), (j, i)] {
+                let atom = &atoms[oxygen];
+                if self.element_of(atom) == 8 && degree[oxygen] == 1 && !atom.is_virtual_site() && !topology.frozen[oxygen] {
+                    let params = topology.params[oxygen].map(|p| [p.a.to_bits(), p.b.to_bits(), p.c.to_bits()]);
+                    let key = (partner, order, atom.formal_charge().to_bits(), params, topology.chi_offsets[oxygen].to_bits());
+                    groups.entry(key).or_default().push(oxygen);
+                }
+            }
+        }
+        for members in groups.values().filter(|m| m.len() > 1) {
+            let mean = members.iter().map(|&o| charges[o]).sum::<f64>() / members.len() as f64;
+            for &o in members {
+                charges[o] = mean;
+            }
+        }
+    }
+
+    /// Equalizes the whole molecule as `compute_charges` does: per connected
+    /// component under `split_components`, otherwise in one pass.
+    pub(crate) fn equalize_molecule(&self, charges: &mut [f64], topology: &Topology) -> (usize, bool) {
+        if self.split_components {
+            self.equalize_components(charges, topology)
+        } else {
+            self.equalize(charges, topology)
         }
+    }
 
-        let mut atom_params: Vec<Option<GasteigerParams>> = Vec::with_capacity(n_atoms);
-        for i in 0..n_atoms {
-            let hybrid = self.guess_hybridization(i, atoms, bonds);
-            let params = get_params(atoms[i].atomic_number(), hybrid)
-                .or_else(|| get_params(atoms[i].atomic_number(), Hybridization::Sp3))
-                .or_else(|| get_params(atoms[i].atomic_number(), Hybridization::Default));
-            atom_params.push(params);
+    /// Runs `equalize` separately on every connected component of `topology`.
+    fn equalize_components(&self, charges: &mut [f64], topology: &Topology) -> (usize, bool) {
+        let (mut iterations, mut converged) = (0, true);
+        for (atoms, sub) in split_topology(topology) {
+            let mut sub_charges: Vec<f64> = atoms.iter().map(|&a| charges[a]).collect();
+            let (k, ok) = self.equalize(&mut sub_charges, &sub);
+            for (&a, q) in atoms.iter().zip(sub_charges) {
+                charges[a] = q;
+            }
+            iterations = iterations.max(k);
+            converged &= ok;
         }
+        (iterations, converged)
+    }
 
-        let mut current_damping = 1.0;
-        for _ in 0..self.iterations {
-            let mut delta_charges = vec![0.0; n_atoms];
+    /// Runs the PEOE iteration in place. Returns `(iterations performed, converged)`.
+    pub(crate) fn equalize(&self, charges: &mut [f64], topology: &Topology) -> (usize, bool) {
+        self.equalize_with(charges, topology, |_, _| {})
+    }
 
-            for bond in bonds {
-                let (i, j) = bond.atom_indices();
-                if i >= n_atoms || j >= n_atoms { continue; }
+    /// Like `equalize`, calling `on_iteration(k, charges)` after each iteration `k` (1-based).
+    pub(crate) fn equalize_with<F>(&self, charges: &mut [f64], topology: &Topology, on_iteration: F) -> (usize, bool)
+    where
+        F: FnMut(usize, &[f64]),
+    {
+        self.equalize_tracked(charges, topology, on_iteration, None)
+    }
+
+    /// Like `equalize_with`, also accumulating into `transfers[p]` the change in
+    /// charge of the first atom of `topology.pairs[p]` (iterative mode only).
+    fn equalize_tracked<F>(
+        &self,
+        charges: &mut [f64],
+        topology: &Topology,
+        mut on_iteration: F,
+        mut transfers: Option<&mut [f64]>,
+    ) -> (usize, bool)
+    where
+        F: FnMut(usize, &[f64]),
+    {
+        if self.mode == SolveMode::Linearized {
+            return self.equalize_linearized(charges, topology, on_iteration);
+        }
+        if let DampingMode::Anderson { depth } = self.damping_mode {
+            return self.equalize_anderson(charges, topology, depth, on_iteration);
+        }
+        let n_atoms = charges.len();
+        let adaptive = self.damping_mode == DampingMode::Adaptive;
+        let mut atom_damping = vec![1.0; n_atoms];
+        let mut prev_delta = vec![0.0; n_atoms];
 
-                if let (Some(p_i), Some(p_j)) = (&atom_params[i], &atom_params[j]) {
-                    let chi_i = self.calculate_electronegativity(p_i, charges[i]);
-                    let chi_j = self.calculate_electronegativity(p_j, charges[j]);
+        let pair_weights = self.pair_weights(n_atoms, topology);
+        let bounded_neighbors = topology.bounds.iter().any(Option::is_some).then(|| unfrozen_neighbors(topology));
 
-                    let chi_plus_i = self.calculate_electronegativity(p_i, 1.0);
-                    let chi_plus_j = self.calculate_electronegativity(p_j, 1.0);
+        let mut current_damping = 1.0;
+        for iteration in 0..self.iterations {
+            let mut delta_charges = vec![0.0; n_atoms];
 
+            for (p, &(i, j)) in topology.pairs.iter().enumerate() {
+                if let (Some(p_i), Some(p_j)) = (&topology.params[i], &topology.params[j]) {
+                    let chi_i = self.calculate_electronegativity(p_i, charges[i]) + topology.chi_offsets[i];
+                    let chi_j = self.calculate_electronegativity(p_j, charges[j]) + topology.chi_offsets[j];
+
+                    let scale = current_damping * f64::min(atom_damping[i], atom_damping[j]) * pair_weights[p];
+                    let mut onto_i = 0.0;
                     if chi_j > chi_i {
-                        let dq = current_damping * (chi_j - chi_i) / chi_plus_i;
+                        let dq = scale * (chi_j - chi_i) / topology.chi_plus[i];
                         delta_charges[i] += dq;
                         delta_charges[j] -= dq;
+                        onto_i = dq;
                     } else if chi_i > chi_j {
-                        let dq = current_damping * (chi_i - chi_j) / chi_plus_j;
+                        let dq = scale * (chi_i - chi_j) / topology.chi_plus[j];
                         delta_charges[j] += dq;
                         delta_charges[i] -= dq;
+                        onto_i = -dq;
+                    }
+                    if let Some(t) = transfers.as_deref_mut() {
+                        t[p] += onto_i;
                     }
                 }
             }
 
             for i in 0..n_atoms {
+                if topology.frozen[i] {
+                    delta_charges[i] = 0.0;
+                }
                 charges[i] += delta_charges[i];
+                if adaptive && delta_charges[i] * prev_delta[i] < 0.0 {
+                    atom_damping[i] *= ADAPTIVE_PENALTY;
+                }
             }
+            if let Some(neighbors) = &bounded_neighbors {
+                apply_bounds(charges, &topology.bounds, &topology.frozen, neighbors);
+            }
+            let update = self.convergence_norm.measure(&delta_charges);
+            prev_delta = delta_charges;
             current_damping *= self.damping;
+            on_iteration(iteration + 1, charges);
+
+            if self.tolerance.is_some_and(|tol| update < tol) {
+                return (iteration + 1, true);
+            }
         }
 
-        charges
+        (self.iterations, false)
+    }
+
+    /// Per-pair transfer weight `1 / f(d)` for `degree_normalization`.
+    fn pair_weights(&self, n_atoms: usize, topology: &Topology) -> Vec<f64> {
+        let f: fn(f64) -> f64 = match self.degree_normalization {
+            DegreeNormalization::None => return vec![1.0; topology.pairs.len()],
+            DegreeNormalization::Degree => |d| d,
+            DegreeNormalization::SqrtDegree => f64::sqrt,
+        };
+        let mut degree = vec![0usize; n_atoms];
+        for &(i, j) in &topology.pairs {
+            degree[i] += 1;
+            degree[j] += 1;
+        }
+        topology.pairs.iter().map(|&(i, j)| 1.0 / f(degree[i].max(degree[j]) as f64)).collect()
     }
 
     fn calculate_electronegativity(&self, p: &GasteigerParams, q: f64) -> f64 {
-        p.a + p.b * q + p.c * q * q
+        self.model.electronegativity(p, q)
     }
 
-    fn guess_hybridization<A, B>(&self, atom_idx: usize, atoms: &[A], bonds: &[B]) -> Hybridization
-    where
-        A: GasteigerAtom,
-        B: GasteigerBond,
-    {
-        let atomic_number = atoms[atom_idx].atomic_number();
-        let mut neighbor_count = 0;
+    /// The hybridization fixed by the atom's type or its own `hybridization()`,
+    /// otherwise the perceived one.
+    fn hybridization_of<A: GasteigerAtom>(&self, atom: &A, orders: &[BondOrder]) -> Hybridization {
+        self.declared_hybridization(atom).unwrap_or_else(|| self.guess_hybridization(self.element_of(atom), orders))
+    }
 
-        for bond in bonds {
-            let (i, j) = bond.atom_indices();
-            if i == atom_idx || j == atom_idx {
-                neighbor_count += 1;
-            }
+    /// The hybridization of a recognized `atom_type` of the atom's element, else
+    /// `hybridization()`.
+    fn declared_hybridization<A: GasteigerAtom>(&self, atom: &A) -> Option<Hybridization> {
+        atom.atom_type()
+            .and_then(atom_type_context)
+            .filter(|&(z, _)| z == self.element_of(atom))
+            .map(|(_, h)| h)
+            .or_else(|| atom.hybridization())
+    }
+
+    /// Bond-order rules used to perceive `atomic_number`'s hybridization: its
+    /// `order_hybridizations` entry if any, otherwise the rules derived from
+    /// `hybridization_thresholds` (empty for elements other than C, N, O and S).
+    pub fn order_hybridization_rules(&self, atomic_number: usize) -> Vec<(f32, Hybridization)> {
+        match self.order_hybridizations.get(&atomic_number) {
+            Some(rules) => rules.clone(),
+            None => self.builtin_thresholds(atomic_number).map_or_else(Vec::new, |t| t.rules().to_vec()),
+        }
+    }
+
+    fn builtin_thresholds(&self, atomic_number: usize) -> Option<BondOrderThresholds> {
+        match atomic_number {
+            6 => Some(self.hybridization_thresholds.carbon),
+            7 => Some(self.hybridization_thresholds.nitrogen),
+            8 => Some(self.hybridization_thresholds.oxygen),
+            16 => Some(self.hybridization_thresholds.sulfur),
+            _ => None,
+        }
+    }
+
+    /// Perceives hybridization from an atom's element and the orders of its bonds.
+    fn guess_hybridization(&self, atomic_number: usize, orders: &[BondOrder]) -> Hybridization {
+        let neighbor_count = orders.len();
+        let max_order = orders.iter().map(|o| o.as_f32()).fold(0.0, f32::max);
+        let first_match = |rules: &[(f32, Hybridization)]| {
+            rules
+                .iter()
+                .find(|&&(min_order, _)| max_order >= min_order)
+                .map(|&(_, h)| h)
+                .unwrap_or_else(|| self.hybridization_from_neighbors(atomic_number, neighbor_count))
+        };
+
+        if let Some(rules) = self.order_hybridizations.get(&atomic_number) {
+            return first_match(rules);
+        }
+        let thresholds = match self.builtin_thresholds(atomic_number) {
+            Some(_) if atomic_number == 16 && neighbor_count > 2 => None,
+            t => t,
+        };
+        let Some(thresholds) = thresholds else {
+            return self.hybridization_from_neighbors(atomic_number, neighbor_count);
+        };
+
+        // Cumulated double bonds (allenes, CO2, azides) are linear like triple bonds.
+        let n_double = orders.iter().filter(|o| o.as_f32() >= thresholds.double).count();
+        if n_double >= 2 && matches!(atomic_number, 6 | 7) {
+            return Hybridization::Sp;
         }
+        // Multiple bonds, when present, take precedence over the neighbor count.
+        first_match(&thresholds.rules())
+    }
 
+    fn hybridization_from_neighbors(&self, atomic_number: usize, neighbor_count: usize) -> Hybridization {
         match atomic_number {
             6 => { // Carbon
                 if neighbor_count >= 4 { Hybridization::Sp3 }
                 else if neighbor_count == 3 { Hybridization::Sp2 }
-                else if neighbor_count <= 2 { Hybridization::Sp }
-                else { Hybridization::Sp3 }
+                else { Hybridization::Sp }
             }
             7 => { // Nitrogen
                 if neighbor_count >= 3 { Hybridization::Sp3 }
@@ -115,7 +1908,218 @@ impl GasteigerSolver {
                 if neighbor_count >= 2 { Hybridization::Sp3 }
                 else { Hybridization::Sp2 }
             }
-            _ => Hybridization::Default,
+            _ => self
+                .element_hybridizations
+                .get(&atomic_number)
+                .copied()
+                .unwrap_or(Hybridization::Default),
+        }
+    }
+}
+
+/// Largest total bond order `validate` accepts for an element, if it checks one.
+fn max_valence(atomic_number: usize) -> Option<f32> {
+    match atomic_number {
+        1 => Some(1.0),
+        6 | 7 => Some(4.0),
+        8 => Some(3.0),
+        _ => None,
+    }
+}
+
+/// Total bond order around an atom as `validate` counts it. Aromatic bonds add
+/// 1 each, plus ½ per bond up to the one double bond a Kekulé structure can
+/// give the atom, and nothing extra if the atom already has a localized double
+/// bond (the 2-pyridone carbon bearing C=O). Two ring bonds thus add 3, and a
+/// fused-ring bridgehead's three add 4 rather than 4.5.
+fn valence_sum(orders: &[BondOrder]) -> f32 {
+    let aromatic = orders.iter().filter(|o| o.is_aromatic()).count() as f32;
+    let localized: f32 = orders.iter().filter(|o| !o.is_aromatic()).map(|o| o.as_f32()).sum();
+    let has_double = orders.iter().any(|o| !o.is_aromatic() && o.as_f32() > 1.75);
+    let kekule_share = if has_double { 0.0 } else { (0.5 * aromatic).min(1.0) };
+    localized + aromatic + kekule_share
+}
+
+/// Collects the orders of the bonds touching each atom in a single pass.
+/// Bond endpoints outside `0..n_atoms` are ignored.
+pub(crate) fn bond_orders_by_atom<A: GasteigerAtom, B: GasteigerBond>(atoms: &[A], bonds: &[B]) -> Vec<Vec<BondOrder>> {
+    let n_atoms = atoms.len();
+    let mut orders = vec![Vec::new(); n_atoms];
+    for bond in bonds {
+        let (i, j) = bond.atom_indices();
+        if [i, j].iter().any(|&k| k < n_atoms && atoms[k].is_virtual_site()) {
+            continue;
+        }
+        let order = perceived_order(bond);
+        if i < n_atoms {
+            orders[i].push(order);
+        }
+        if j < n_atoms && j != i {
+            orders[j].push(order);
+        }
+    }
+    orders
+}
+
+/// Moves the charge of every real atom bonded to virtual sites onto those sites,
+/// split equally. Returns the indices of all virtual sites.
+fn relocate_to_virtual_sites<A: GasteigerAtom, B: GasteigerBond>(atoms: &[A], bonds: &[B], charges: &mut [f64]) -> Vec<usize> {
+    let sites: Vec<usize> = (0..atoms.len()).filter(|&i| atoms[i].is_virtual_site()).collect();
+    if sites.is_empty() {
+        return sites;
+    }
+    let mut sites_by_host: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, j) in bonds.iter().map(|b| b.atom_indices()) {
+        if i >= atoms.len() || j >= atoms.len() {
+            continue;
+        }
+        match (atoms[i].is_virtual_site(), atoms[j].is_virtual_site()) {
+            (false, true) => sites_by_host.entry(i).or_default().push(j),
+            (true, false) => sites_by_host.entry(j).or_default().push(i),
+            _ => {}
+        }
+    }
+    for (host, host_sites) in sites_by_host {
+        let share = charges[host] / host_sites.len() as f64;
+        charges[host] = 0.0;
+        for site in host_sites {
+            charges[site] += share;
+        }
+    }
+    sites
+}
+
+/// Splits `topology` into one sub-topology per connected component that has
+/// bonds, ordered by lowest atom index, each with its atoms' global indices.
+fn split_topology(topology: &Topology) -> Vec<(Vec<usize>, Topology)> {
+    let n = topology.params.len();
+    let labels = component_labels(n, topology.pairs.iter().copied());
+    let mut local = vec![usize::MAX; n];
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (atom, &label) in labels.iter().enumerate() {
+        let group = members.entry(label).or_default();
+        local[atom] = group.len();
+        group.push(atom);
+    }
+
+    let mut pairs_by_component: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    for &(i, j) in &topology.pairs {
+        pairs_by_component.entry(labels[i]).or_default().push((local[i], local[j]));
+    }
+    let mut components: Vec<(usize, Vec<(usize, usize)>)> = pairs_by_component.into_iter().collect();
+    components.sort_unstable_by_key(|&(label, _)| label);
+
+    components
+        .into_iter()
+        .map(|(label, pairs)| {
+            let atoms = members.remove(&label).unwrap_or_default();
+            let sub = Topology {
+                params: atoms.iter().map(|&a| topology.params[a]).collect(),
+                chi_plus: atoms.iter().map(|&a| topology.chi_plus[a]).collect(),
+                pairs,
+                bounds: atoms.iter().map(|&a| topology.bounds[a]).collect(),
+                frozen: atoms.iter().map(|&a| topology.frozen[a]).collect(),
+                chi_offsets: atoms.iter().map(|&a| topology.chi_offsets[a]).collect(),
+            };
+            (atoms, sub)
+        })
+        .collect()
+}
+
+/// Fisher-Yates shuffle driven by SplitMix64, so a seed gives the same order everywhere.
+fn shuffle_seeded<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+    for i in (1..items.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// A bond with its endpoints renumbered into a fragment.
+struct LocalBond<'a, B> {
+    bond: &'a B,
+    pair: (usize, usize),
+}
+
+impl<B: GasteigerBond> GasteigerBond for LocalBond<'_, B> {
+    fn atom_indices(&self) -> (usize, usize) { self.pair }
+    fn bond_order(&self) -> f32 { self.bond.bond_order() }
+    fn is_periodic_image(&self) -> bool { self.bond.is_periodic_image() }
+    fn bond_kind(&self) -> BondKind { self.bond.bond_kind() }
+    fn typed_order(&self) -> Option<BondOrder> { self.bond.typed_order() }
+}
+
+/// An atom with an extra amount added to its formal charge.
+struct ExtraCharge<'a, A> {
+    atom: &'a A,
+    extra: f32,
+}
+
+impl<A: GasteigerAtom> GasteigerAtom for ExtraCharge<'_, A> {
+    fn atomic_number(&self) -> usize { self.atom.atomic_number() }
+    fn formal_charge(&self) -> f32 { self.atom.formal_charge() + self.extra }
+    fn is_virtual_site(&self) -> bool { self.atom.is_virtual_site() }
+    fn is_frozen(&self) -> bool { self.atom.is_frozen() }
+    fn hybridization(&self) -> Option<Hybridization> { self.atom.hybridization() }
+    fn atom_type(&self) -> Option<&str> { self.atom.atom_type() }
+}
+
+/// For every atom, its bonded, parameterized neighbors that are not frozen.
+pub(crate) fn unfrozen_neighbors(topology: &Topology) -> Vec<Vec<usize>> {
+    let mut neighbors = vec![Vec::new(); topology.params.len()];
+    for &(i, j) in &topology.pairs {
+        if !topology.frozen[j] {
+            neighbors[i].push(j);
+        }
+        if !topology.frozen[i] {
+            neighbors[j].push(i);
+        }
+    }
+    neighbors
+}
+
+/// Clamps charges into their bounds, returning the clipped charge to the
+/// atom's unfrozen neighbors in equal shares. Frozen atoms are never changed.
+pub(crate) fn apply_bounds(charges: &mut [f64], bounds: &[Option<(f64, f64)>], frozen: &[bool], neighbors: &[Vec<usize>]) {
+    for (i, bound) in bounds.iter().enumerate() {
+        let Some((lo, hi)) = *bound else { continue; };
+        if frozen[i] || neighbors[i].is_empty() {
+            continue;
+        }
+        let excess = charges[i] - charges[i].clamp(lo, hi);
+        if excess != 0.0 {
+            charges[i] -= excess;
+            let share = excess / neighbors[i].len() as f64;
+            for &j in &neighbors[i] {
+                charges[j] += share;
+            }
+        }
+    }
+}
+
+/// Parameterized atoms that have bonds, none of which survived into `topology.pairs`.
+fn orphaned_atoms<B: GasteigerBond>(topology: &Topology, bonds: &[B]) -> Vec<usize> {
+    let n_atoms = topology.params.len();
+    let mut bonded = vec![false; n_atoms];
+    let mut paired = vec![false; n_atoms];
+    for (i, j) in bonds.iter().map(|b| b.atom_indices()) {
+        if i < n_atoms && j < n_atoms {
+            bonded[i] = true;
+            bonded[j] = true;
         }
     }
-}
\ No newline at end of file
+    for &(i, j) in &topology.pairs {
+        paired[i] = true;
+        paired[j] = true;
+    }
+    (0..n_atoms)
+        .filter(|&i| topology.params[i].is_some() && bonded[i] && !paired[i])
+        .collect()
+}
@@ -0,0 +1,88 @@
+//! Graph helpers over bond lists.
+
+/// Labels each atom with the smallest atom index of its connected component.
+/// Pairs with an endpoint outside `0..n_atoms` are ignored.
+pub(crate) fn component_labels<I>(n_atoms: usize, pairs: I) -> Vec<usize>
+where
+    I: IntoIterator<Item = (usize, usize)>,
+{
+    let mut parent: Vec<usize> = (0..n_atoms).collect();
+
+    fn find(parent: &mut [usize], mut x: usize) -> usize {
+        while parent[x] != x {
+            parent[x] = parent[parent[x]];
+            x = parent[x];
+        }
+        x
+    }
+
+    for (i, j) in pairs {
+        if i >= n_atoms || j >= n_atoms { continue; }
+        let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+        // Keep the smaller index as root so labels are canonical.
+        if ri < rj { parent[rj] = ri; } else if rj < ri { parent[ri] = rj; }
+    }
+    (0..n_atoms).map(|i| find(&mut parent, i)).collect()
+}
+
+/// Whether a bond order denotes an aromatic (delocalized) bond.
+pub(crate) fn is_aromatic_order(order: f32) -> bool {
+    (order - 1.5).abs() < 0.25
+}
+
+/// Finds a ring containing an aromatic bond that cannot be closed through
+/// aromatic bonds alone, i.e. a ring mixing aromatic and Kekulé/single labels.
+/// Returns the atoms of the smallest such ring, in path order.
+pub(crate) fn find_inconsistent_aromatic_ring(n_atoms: usize, bonds: &[(usize, usize, f32)]) -> Option<Vec<usize>> {
+    let mut adjacency: Vec<Vec<(usize, usize)>> = vec![Vec::new(); n_atoms];
+    for (k, &(i, j, _)) in bonds.iter().enumerate() {
+        if i < n_atoms && j < n_atoms && i != j {
+            adjacency[i].push((j, k));
+            adjacency[j].push((i, k));
+        }
+    }
+
+    for (k, &(u, v, order)) in bonds.iter().enumerate() {
+        if !is_aromatic_order(order) || u >= n_atoms || v >= n_atoms || u == v {
+            continue;
+        }
+        let aromatic_only = |edge: usize| is_aromatic_order(bonds[edge].2);
+        if shortest_path(&adjacency, u, v, k, aromatic_only).is_some() {
+            continue;
+        }
+        if let Some(ring) = shortest_path(&adjacency, u, v, k, |_| true) {
+            return Some(ring);
+        }
+    }
+    None
+}
+
+/// Breadth-first path from `start` to `goal` that never uses bond `skip`
+/// and only uses bonds accepted by `allowed`.
+fn shortest_path<F>(adjacency: &[Vec<(usize, usize)>], start: usize, goal: usize, skip: usize, allowed: F) -> Option<Vec<usize>>
+where
+    F: Fn(usize) -> bool,
+{
+    let mut previous = vec![usize::MAX; adjacency.len()];
+    previous[start] = start;
+    let mut queue = std::collections::VecDeque::from([start]);
+    while let Some(atom) = queue.pop_front() {
+        if atom == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while current != start {
+                current = previous[current];
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &(next, edge) in &adjacency[atom] {
+            if edge != skip && previous[next] == usize::MAX && allowed(edge) {
+                previous[next] = atom;
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
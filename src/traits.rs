@@ -1,11 +1,78 @@
+use crate::parameters::{GasteigerParams, Hybridization};
+use crate::topology::is_aromatic_order;
+
 /// Trait for atoms that need Gasteiger partial charges.
 pub trait GasteigerAtom {
     /// Atomic number (e.g., H=1, C=6).
     fn atomic_number(&self) -> usize;
     /// Formal charge of the atom (default is 0.0).
+    /// Fractional values are allowed, e.g. ±0.5 for a radical modeled as a split
+    /// charge; the solver conserves whatever total they sum to.
     fn formal_charge(&self) -> f32 {
         0.0
     }
+    /// Whether this is an off-site charge (lone pair, TIP4P M site) rather than a
+    /// nucleus (default is false). Virtual sites are ignored by perception and have
+    /// no parameters; after equalization the charge of each real atom bonded to
+    /// virtual sites is moved onto them in equal shares.
+    fn is_virtual_site(&self) -> bool {
+        false
+    }
+    /// Whether this atom keeps its formal charge as a fixed charge (default is
+    /// false). Frozen atoms still pull on their neighbors through their χ, but
+    /// their own charge is never updated, so the total charge is not conserved
+    /// across bonds to them. Honored by every solve mode (iterative, Anderson
+    /// and linearized) and by `charge_bounds` clamping.
+    fn is_frozen(&self) -> bool {
+        false
+    }
+    /// Hybridization to use instead of perceiving one (default is `None`).
+    ///
+    /// Precedence, highest first: the vector passed to
+    /// `compute_charges_with_hybridizations`, then a recognized `atom_type`,
+    /// then this method, then perception from bond orders (including
+    /// `element_hybridizations`). An explicit value also disables
+    /// fractional-order blending for the atom; the parameter fallback chain
+    /// still applies.
+    fn hybridization(&self) -> Option<Hybridization> {
+        None
+    }
+    /// Force-field atom type such as `"C.3"` or `"ca"` (default is `None`).
+    ///
+    /// Types listed by `parameters::atom_type_context` whose element matches
+    /// the atom's own (after `element_aliases`) fix its hybridization, with the
+    /// same effect as returning it from `hybridization`; unknown or mismatched
+    /// types are ignored.
+    fn atom_type(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<T: GasteigerAtom + ?Sized> GasteigerAtom for &T {
+    fn atomic_number(&self) -> usize { (**self).atomic_number() }
+    fn formal_charge(&self) -> f32 { (**self).formal_charge() }
+    fn is_virtual_site(&self) -> bool { (**self).is_virtual_site() }
+    fn is_frozen(&self) -> bool { (**self).is_frozen() }
+    fn hybridization(&self) -> Option<Hybridization> { (**self).hybridization() }
+    fn atom_type(&self) -> Option<&str> { (**self).atom_type() }
+}
+
+/// Atoms that can store a computed partial charge.
+pub trait PartialChargeSink {
+    fn set_partial_charge(&mut self, charge: f64);
+}
+
+/// Atoms carrying a stable identifier (e.g. a PDB serial number).
+pub trait IdentifiedAtom: GasteigerAtom {
+    /// Unique identifier of the atom within its molecule.
+    fn id(&self) -> u64;
+}
+
+/// Atoms with a position in space, needed by coordinate-based writers such as
+/// `pqr::write_pqr`.
+pub trait PositionedAtom: GasteigerAtom {
+    /// Cartesian coordinates in Å.
+    fn position(&self) -> [f64; 3];
 }
 
 /// Trait for bonds between atoms.
@@ -14,4 +81,151 @@ pub trait GasteigerBond {
     fn atom_indices(&self) -> (usize, usize);
     /// Bond order (1.0 for single, 2.0 for double, 3.0 for triple, 1.5 for aromatic).
     fn bond_order(&self) -> f32;
+    /// Whether this bond crosses a periodic cell boundary (default is false).
+    /// Charges are unaffected; flagged bonds are listed in `ChargeResult::periodic_bonds`.
+    fn is_periodic_image(&self) -> bool {
+        false
+    }
+    /// Categorical bond type; derived from `bond_order` by default.
+    ///
+    /// Override it to state intent explicitly. Whenever it disagrees with
+    /// `BondKind::from_order(self.bond_order())`, perception uses the kind's
+    /// nominal order instead of `bond_order`.
+    fn bond_kind(&self) -> BondKind {
+        BondKind::from_order(self.bond_order())
+    }
+    /// Bond order as an enum, if the bond carries one (default is `None`).
+    ///
+    /// When `Some`, it takes precedence over both `bond_order` and `bond_kind`
+    /// during perception, so e.g. `BondOrder::Aromatic` is recognized as
+    /// aromatic without comparing floats.
+    fn typed_order(&self) -> Option<BondOrder> {
+        None
+    }
+}
+
+/// Categorical bond type, an alternative to numeric bond orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BondKind {
+    Single,
+    Double,
+    Triple,
+    Aromatic,
+}
+
+impl BondKind {
+    /// Classifies a numeric order with the default perception thresholds:
+    /// below 1.25 is single, below 1.75 aromatic, below 2.5 double, else triple.
+    pub fn from_order(order: f32) -> Self {
+        if order >= 2.5 {
+            BondKind::Triple
+        } else if order >= 1.75 {
+            BondKind::Double
+        } else if order >= 1.25 {
+            BondKind::Aromatic
+        } else {
+            BondKind::Single
+        }
+    }
+
+    /// Nominal numeric order: 1.0, 2.0, 3.0 or 1.5.
+    pub fn order(self) -> f32 {
+        match self {
+            BondKind::Single => 1.0,
+            BondKind::Double => 2.0,
+            BondKind::Triple => 3.0,
+            BondKind::Aromatic => 1.5,
+        }
+    }
+}
+
+/// A bond order: one of the nominal kinds, or any other value as `Partial`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BondOrder {
+    Single,
+    Double,
+    Triple,
+    Aromatic,
+    /// A fractional or otherwise non-nominal order, e.g. 1.33 for a delocalized
+    /// carboxylate bond or a Wiberg bond index.
+    Partial(f32),
+}
+
+impl BondOrder {
+    /// Exactly 1.0, 2.0, 3.0 and 1.5 map to the named variants; anything else
+    /// is `Partial`.
+    pub fn from_f32(order: f32) -> Self {
+        match order {
+            1.0 => BondOrder::Single,
+            2.0 => BondOrder::Double,
+            3.0 => BondOrder::Triple,
+            1.5 => BondOrder::Aromatic,
+            _ => BondOrder::Partial(order),
+        }
+    }
+
+    /// Numeric order: 1.0, 2.0, 3.0, 1.5, or the `Partial` value.
+    pub fn as_f32(self) -> f32 {
+        match self {
+            BondOrder::Partial(order) => order,
+            BondOrder::Single => 1.0,
+            BondOrder::Double => 2.0,
+            BondOrder::Triple => 3.0,
+            BondOrder::Aromatic => 1.5,
+        }
+    }
+
+    /// Whether this denotes an aromatic bond. A `Partial` order counts when it
+    /// lies within 0.25 of 1.5.
+    pub fn is_aromatic(self) -> bool {
+        match self {
+            BondOrder::Aromatic => true,
+            BondOrder::Partial(order) => is_aromatic_order(order),
+            _ => false,
+        }
+    }
+}
+
+impl From<BondKind> for BondOrder {
+    fn from(kind: BondKind) -> Self {
+        match kind {
+            BondKind::Single => BondOrder::Single,
+            BondKind::Double => BondOrder::Double,
+            BondKind::Triple => BondOrder::Triple,
+            BondKind::Aromatic => BondOrder::Aromatic,
+        }
+    }
+}
+
+/// The order perception should use: `typed_order` if given, else
+/// `bond_order`, unless an explicit `bond_kind` contradicts it.
+pub(crate) fn perceived_order<B: GasteigerBond + ?Sized>(bond: &B) -> BondOrder {
+    if let Some(order) = bond.typed_order() {
+        return order;
+    }
+    let order = bond.bond_order();
+    let kind = bond.bond_kind();
+    if kind == BondKind::from_order(order) { BondOrder::from_f32(order) } else { kind.into() }
 }
+
+/// `perceived_order` as a number.
+pub(crate) fn effective_order<B: GasteigerBond + ?Sized>(bond: &B) -> f32 {
+    perceived_order(bond).as_f32()
+}
+
+/// Receives the solver's per-atom parameter decisions, e.g. for debugging.
+pub trait ResolutionObserver {
+    /// Called once per atom with its perceived hybridization and the parameters
+    /// finally used (`None` if the atom is a spectator).
+    fn on_atom_resolved(
+        &mut self,
+        _index: usize,
+        _atomic_number: usize,
+        _guessed: Hybridization,
+        _resolved: Option<&GasteigerParams>,
+    ) {
+    }
+}
+
+/// No-op observer.
+impl ResolutionObserver for () {}